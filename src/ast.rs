@@ -1,4 +1,7 @@
+use crate::bigint::BigInt;
 use crate::lexer;
+use crate::span::Span;
+use std::fmt;
 pub type Identifier = lexer::Token;
 #[derive(PartialEq, Clone, Debug)]
 pub enum Prefix {
@@ -8,54 +11,241 @@ pub enum Prefix {
     Cardinal,
 }
 
+#[derive(PartialEq, Clone, Debug)]
+pub enum Postfix {
+    Cast(Cast),
+    /// A named member, such as the variant `Dead` in `Cell.Dead` or the
+    /// field `name` in `person.name`.
+    Field(String),
+    /// A position in a list, as in `pair.0`.
+    Index(usize),
+    /// `?`, unwrapping `Ok` or returning early with `Error` or `None`.
+    Try,
+}
+
+/// The types a value can be converted to with a postfix cast, as in `xs.set`.
+#[derive(PartialEq, Clone, Debug)]
+pub enum Cast {
+    Set,
+    Int,
+    Float,
+    String,
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum Infix {
     Plus,
     Minus,
     Divide,
+    /// `÷`, which divides integers into an exact fraction.
+    ExactDivide,
     Multiply,
     Exponent,
     Equal,
     NotEqual,
     GreaterThan,
     LessThan,
+    GreaterEqual,
+    LessEqual,
     Modulo,
     Cons,
+    Concat,
+    Union,
+    Intersection,
+    SymmetricDifference,
+    Member,
+    Pipe,
+}
+
+impl Infix {
+    /// Whether this is one of `==`, `!=`, `<`, `>`, `<=` and `>=`.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Infix::Equal
+                | Infix::NotEqual
+                | Infix::LessThan
+                | Infix::GreaterThan
+                | Infix::LessEqual
+                | Infix::GreaterEqual
+        )
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Prefix::Plus => write!(f, "+"),
+            Prefix::Minus => write!(f, "-"),
+            Prefix::Not => write!(f, "!"),
+            Prefix::Cardinal => write!(f, "#"),
+        }
+    }
+}
+
+impl fmt::Display for Postfix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Postfix::Cast(cast) => write!(f, ".{}", cast),
+            Postfix::Field(name) => write!(f, ".{}", name),
+            Postfix::Index(index) => write!(f, ".{}", index),
+            Postfix::Try => write!(f, "?"),
+        }
+    }
+}
+
+impl fmt::Display for Cast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cast::Set => write!(f, "set"),
+            Cast::Int => write!(f, "int"),
+            Cast::Float => write!(f, "float"),
+            Cast::String => write!(f, "string"),
+        }
+    }
+}
+
+impl fmt::Display for Infix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Infix::Plus => write!(f, "+"),
+            Infix::Minus => write!(f, "-"),
+            Infix::Divide => write!(f, "/"),
+            Infix::ExactDivide => write!(f, "over"),
+            Infix::Multiply => write!(f, "*"),
+            Infix::Exponent => write!(f, "**"),
+            Infix::Equal => write!(f, "=="),
+            Infix::NotEqual => write!(f, "!="),
+            Infix::GreaterThan => write!(f, ">"),
+            Infix::LessThan => write!(f, "<"),
+            Infix::GreaterEqual => write!(f, ">="),
+            Infix::LessEqual => write!(f, "<="),
+            Infix::Modulo => write!(f, "%"),
+            Infix::Cons => write!(f, "::"),
+            Infix::Concat => write!(f, "++"),
+            Infix::Union => write!(f, "\\/"),
+            Infix::Intersection => write!(f, "/\\"),
+            Infix::SymmetricDifference => write!(f, "^"),
+            Infix::Member => write!(f, "in"),
+            Infix::Pipe => write!(f, "|>"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
 }
 
-#[derive(Debug)]
-pub enum Statement {
+#[derive(Debug, Clone)]
+pub enum StatementKind {
     Let(String, Expression),
     Return(Expression),
     Comment(String),
     Expression(Expression),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Literal {
-    Integer(i64),
+    Integer(BigInt),
+    Float(f64),
     String(String),
     Boolean(bool),
     List(Vec<Expression>),
+    /// `{name: "Ada", "born": 1815}`, keyed by field names or strings.
+    Map(Vec<(String, Expression)>),
 }
 
-#[derive(Debug)]
-pub enum Expression {
+/// One alternative of a `type`, as in `| Alive of Int`, where `payload` names
+/// the type of the value the variant carries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub tag: String,
+    pub payload: Option<String>,
+}
+
+/// The shape a value is compared against in a `match` arm. Names starting
+/// with an uppercase letter refer to variants, other names bind the value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// `_`
+    Wildcard,
+    Binding(String),
+    Integer(BigInt),
+    String(String),
+    Boolean(bool),
+    /// `Alive n`, `Cell.Dormant`
+    Variant {
+        type_name: Option<String>,
+        tag: String,
+        payload: Option<Box<Pattern>>,
+    },
+    /// `head :: tail`
+    Cons(Box<Pattern>, Box<Pattern>),
+    /// `{}`, `[x, y]`
+    List(Vec<Pattern>),
+}
+
+/// `| <pattern> -> <body>`, where `span` covers the pattern.
+#[derive(Debug, Clone)]
+pub struct Arm {
+    pub pattern: Pattern,
+    pub body: Expression,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
     None,
     Identifier(Identifier),
     // TODO: Refactor Ok,Error, None to a `Types` enum child of Expression
     Ok(Box<Expression>),
-    Error,
+    // Error, Error "message"
+    Error(Option<Box<Expression>>),
     Unit,
-    Type(Vec<Identifier>),
+    // type | Alive of Int | Dead of Int | Dormant
+    Type(Vec<Variant>),
     Literal(Literal),
     Prefix(Prefix, Box<Expression>),
     Infix(Infix, Box<Expression>, Box<Expression>),
-    // for { x in [0..10] : x * x };
-    // TODO: For loops
+    Postfix(Postfix, Box<Expression>),
+    // s.[0], xs.[1..3)
+    Index {
+        collection: Box<Expression>,
+        index: Box<Expression>,
+    },
+    // "count: {n}", as its text and expressions in order
+    Interpolation(Vec<Expression>),
+    // {person with age: 37}
+    Update {
+        record: Box<Expression>,
+        fields: Vec<(String, Expression)>,
+    },
+    // [0..10], [0..10), [0..20..2]
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        step: Option<Box<Expression>>,
+        inclusive: bool,
+    },
+    // for { x <- [0..10], x % 2 == 0 : x * x };
     For {
-        map: Box<Expression>,
+        binder: String,
         domain: Box<Expression>,
+        filters: Vec<Expression>,
+        map: Box<Expression>,
+    },
+    // match light | Red -> Green | _ -> Red
+    Match {
+        scrutinee: Box<Expression>,
+        arms: Vec<Arm>,
     },
     If {
         condition: Box<Expression>,
@@ -0,0 +1,510 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// An integer of any size, as a sign and the base 2^32 digits of its
+/// magnitude, least significant first. The magnitude has no leading zero
+/// digits and zero is never negative, so equal values have equal fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+/// The largest power of ten that fits in a digit, for converting to and
+/// from decimal nine places at a time.
+const DECIMAL_BASE: u32 = 1_000_000_000;
+
+impl BigInt {
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> BigInt {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        BigInt {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// The number of bits in the magnitude, which is zero for zero.
+    pub fn bits(&self) -> usize {
+        match self.digits.last() {
+            Some(last) => self.digits.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// The value as an `i64`, if it fits in one.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u64, |high, &digit| high << 32 | u64::from(digit));
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.digits.iter().rev().fold(0.0, |high, &digit| {
+            high * 4_294_967_296.0 + f64::from(digit)
+        });
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// The whole part of a finite `value`, rounding towards zero.
+    pub fn from_f64(value: f64) -> Option<BigInt> {
+        if !value.is_finite() {
+            return None;
+        }
+        let whole = value.abs().trunc();
+        if whole < 1.0 {
+            return Some(BigInt::from(0i64));
+        }
+        // A normal float is `1.mantissa * 2^(exponent - 1023)`.
+        let bits = whole.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i64 - 1075;
+        let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+        let magnitude = if exponent < 0 {
+            BigInt::from((mantissa >> -exponent) as i64)
+        } else {
+            BigInt::from(mantissa as i64).shift_left(exponent as usize)
+        };
+        Some(if value < 0.0 { -&magnitude } else { magnitude })
+    }
+
+    /// Parse an optionally signed run of decimal digits.
+    pub fn parse(text: &str) -> Option<BigInt> {
+        let (negative, decimal) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if decimal.is_empty() || !decimal.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let mut digits = vec![];
+        for chunk in decimal.as_bytes().chunks(9) {
+            let value: u32 = std::str::from_utf8(chunk).ok()?.parse().ok()?;
+            multiply_add_small(&mut digits, 10u32.pow(chunk.len() as u32), value);
+        }
+        Some(BigInt::from_parts(negative, digits))
+    }
+
+    /// Multiply by `2^bits`.
+    pub fn shift_left(&self, bits: usize) -> BigInt {
+        let mut digits = vec![0; bits / 32];
+        digits.extend(shift_digits_left(&self.digits, (bits % 32) as u32));
+        BigInt::from_parts(self.negative, digits)
+    }
+
+    /// The quotient rounded towards zero and the remainder, which takes the
+    /// sign of `self`, as `/` and `%` do for `i64`. `other` must not be zero.
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, remainder) = divide_digits(&self.digits, &other.digits);
+        (
+            BigInt::from_parts(self.negative != other.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        )
+    }
+
+    /// Raise to a power by repeated squaring.
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        let mut base = self.clone();
+        let mut result = BigInt::from(1i64);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+
+    /// The greatest common divisor, which is never negative.
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let remainder = a.div_rem(&b).1;
+            (a, b) = (b, remainder);
+        }
+        a
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.digits.clone())
+    }
+}
+
+fn compare_digits(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let (long, short) = if left.len() >= right.len() {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+    for (index, &digit) in long.iter().enumerate() {
+        let total = u64::from(digit) + u64::from(*short.get(index).unwrap_or(&0)) + carry;
+        sum.push(total as u32);
+        carry = total >> 32;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+/// `left - right`, where `left` is at least as large as `right`.
+fn subtract_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (index, &digit) in left.iter().enumerate() {
+        let total = i64::from(digit) - i64::from(*right.get(index).unwrap_or(&0)) - borrow;
+        difference.push(total as u32);
+        borrow = i64::from(total < 0);
+    }
+    difference
+}
+
+fn multiply_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; left.len() + right.len()];
+    for (i, &a) in left.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &b) in right.iter().enumerate() {
+            let total = u64::from(a) * u64::from(b) + u64::from(product[i + j]) + carry;
+            product[i + j] = total as u32;
+            carry = total >> 32;
+        }
+        product[i + right.len()] = carry as u32;
+    }
+    product
+}
+
+/// `digits = digits * factor + addend`, in place.
+fn multiply_add_small(digits: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = u64::from(addend);
+    for digit in digits.iter_mut() {
+        let total = u64::from(*digit) * u64::from(factor) + carry;
+        *digit = total as u32;
+        carry = total >> 32;
+    }
+    if carry > 0 {
+        digits.push(carry as u32);
+    }
+}
+
+/// Divide by a single nonzero digit, giving the quotient and remainder.
+fn divide_small(digits: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; digits.len()];
+    let mut remainder = 0u64;
+    for (index, &digit) in digits.iter().enumerate().rev() {
+        let dividend = remainder << 32 | u64::from(digit);
+        quotient[index] = (dividend / u64::from(divisor)) as u32;
+        remainder = dividend % u64::from(divisor);
+    }
+    (quotient, remainder as u32)
+}
+
+/// Shift left by fewer than 32 bits, growing by a digit for the overflow.
+fn shift_digits_left(digits: &[u32], bits: u32) -> Vec<u32> {
+    let mut shifted = Vec::with_capacity(digits.len() + 1);
+    let mut carry = 0u32;
+    for &digit in digits {
+        shifted.push(digit << bits | carry);
+        carry = if bits == 0 { 0 } else { digit >> (32 - bits) };
+    }
+    shifted.push(carry);
+    shifted
+}
+
+/// Shift right by fewer than 32 bits.
+fn shift_digits_right(digits: &[u32], bits: u32) -> Vec<u32> {
+    let mut shifted = vec![0u32; digits.len()];
+    for index in 0..digits.len() {
+        let high = match digits.get(index + 1) {
+            Some(&next) if bits > 0 => next << (32 - bits),
+            _ => 0,
+        };
+        shifted[index] = digits[index] >> bits | high;
+    }
+    shifted
+}
+
+/// Long division of magnitudes by a nonzero `divisor`, following Knuth's
+/// Algorithm D (The Art of Computer Programming, volume 2, 4.3.1).
+fn divide_digits(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compare_digits(dividend, divisor) == Ordering::Less {
+        return (vec![], dividend.to_vec());
+    }
+    if let [divisor] = divisor {
+        let (quotient, remainder) = divide_small(dividend, *divisor);
+        return (quotient, vec![remainder]);
+    }
+    // Normalize so the divisor's top digit has its high bit set, which
+    // keeps each estimated quotient digit at most two too large.
+    let n = divisor.len();
+    let m = dividend.len() - n;
+    let shift = divisor[n - 1].leading_zeros();
+    let mut v = shift_digits_left(divisor, shift);
+    v.truncate(n);
+    let mut u = shift_digits_left(dividend, shift);
+    let mut quotient = vec![0u32; m + 1];
+    let base = 1u64 << 32;
+    for j in (0..=m).rev() {
+        let top = u64::from(u[j + n]) << 32 | u64::from(u[j + n - 1]);
+        let mut estimate = top / u64::from(v[n - 1]);
+        let mut rest = top % u64::from(v[n - 1]);
+        while estimate >= base
+            || estimate * u64::from(v[n - 2]) > (rest << 32 | u64::from(u[j + n - 2]))
+        {
+            estimate -= 1;
+            rest += u64::from(v[n - 1]);
+            if rest >= base {
+                break;
+            }
+        }
+        // Subtract `estimate * v` from the current window of `u`.
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let product = estimate * u64::from(v[i]) + carry;
+            carry = product >> 32;
+            let total = i64::from(u[i + j]) - borrow - (product & 0xffff_ffff) as i64;
+            u[i + j] = total as u32;
+            borrow = i64::from(total < 0);
+        }
+        let total = i64::from(u[j + n]) - borrow - carry as i64;
+        u[j + n] = total as u32;
+        // The estimate was one too large: add the divisor back once.
+        if total < 0 {
+            estimate -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u64::from(u[i + j]) + u64::from(v[i]) + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+    (quotient, shift_digits_right(&u[..n], shift))
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> BigInt {
+        let magnitude = value.unsigned_abs();
+        BigInt::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl From<u64> for BigInt {
+    fn from(value: u64) -> BigInt {
+        BigInt::from_parts(false, vec![value as u32, (value >> 32) as u32])
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(value: u128) -> BigInt {
+        let digits = (0..4).map(|index| (value >> (32 * index)) as u32).collect();
+        BigInt::from_parts(false, digits)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.digits.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_digits(&self.digits, &other.digits));
+        }
+        // Opposite signs: the larger magnitude decides the sign.
+        match compare_digits(&self.digits, &other.digits) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, subtract_digits(&other.digits, &self.digits))
+            }
+            _ => BigInt::from_parts(self.negative, subtract_digits(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            multiply_digits(&self.digits, &other.digits),
+        )
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_digits(&self.digits, &other.digits),
+            (true, true) => compare_digits(&other.digits, &self.digits),
+        }
+    }
+}
+
+/// Decimal, peeling off nine digits at a time from the least significant.
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = vec![];
+        let mut digits = self.digits.clone();
+        while !digits.is_empty() {
+            let (quotient, remainder) = divide_small(&digits, DECIMAL_BASE);
+            chunks.push(remainder);
+            digits = BigInt::from_parts(false, quotient).digits;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(text: &str) -> BigInt {
+        BigInt::parse(text).unwrap()
+    }
+
+    #[test]
+    fn division_adds_back_when_the_estimate_is_too_large() {
+        // In base 2^32, dividing [0, 0, 2^31, 2^31 - 1] by [1, 0, 2^31]
+        // overestimates the quotient digit by one after the usual checks.
+        let dividend = BigInt::from_parts(false, vec![0, 0, 0x8000_0000, 0x7fff_ffff]);
+        let divisor = BigInt::from_parts(false, vec![1, 0, 0x8000_0000]);
+        let (quotient, remainder) = dividend.div_rem(&divisor);
+        assert_eq!(quotient, BigInt::from(4_294_967_294i64));
+        assert_eq!(remainder, big("39614081257132168792477007874"));
+        assert_eq!(&(&quotient * &divisor) + &remainder, dividend);
+    }
+
+    #[test]
+    fn division_truncates_towards_zero() {
+        let (dividend, divisor) = (big("-100000000000000000000000"), big("7000000000000"));
+        let (quotient, remainder) = dividend.div_rem(&divisor);
+        assert_eq!(quotient, big("-14285714285"));
+        assert_eq!(remainder, big("-5000000000000"));
+        assert_eq!(&(&quotient * &divisor) + &remainder, dividend);
+    }
+
+    #[test]
+    fn to_i64_at_the_limits() {
+        assert_eq!(BigInt::from(i64::MAX).to_i64(), Some(i64::MAX));
+        assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!(
+            (&BigInt::from(i64::MAX) + &BigInt::from(1i64)).to_i64(),
+            None
+        );
+        assert_eq!(
+            (&BigInt::from(i64::MIN) - &BigInt::from(1i64)).to_i64(),
+            None
+        );
+        assert_eq!(BigInt::from(u64::MAX).to_i64(), None);
+    }
+
+    #[test]
+    fn from_f64_keeps_the_whole_part() {
+        assert_eq!(BigInt::from_f64(0.75), Some(BigInt::from(0i64)));
+        assert_eq!(
+            BigInt::from_f64(-2.5e20),
+            Some(big("-250000000000000000000"))
+        );
+        assert_eq!(
+            BigInt::from_f64(9_223_372_036_854_775_808.0),
+            Some(big("9223372036854775808"))
+        );
+        assert_eq!(
+            BigInt::from_f64(1e300).map(|value| value.to_string().len()),
+            Some(301)
+        );
+        assert_eq!(BigInt::from_f64(f64::NAN), None);
+        assert_eq!(BigInt::from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for text in [
+            "0",
+            "-1",
+            "999999999",
+            "1000000000",
+            "-9223372036854775808",
+            "18446744073709551616",
+            "15511210043330985984000000",
+            "-1000000000000000000000000000000000000001",
+        ] {
+            assert_eq!(big(text).to_string(), text);
+        }
+        assert_eq!(big("+0042").to_string(), "42");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(BigInt::parse(""), None);
+        assert_eq!(BigInt::parse("12a"), None);
+        assert_eq!(BigInt::parse("-"), None);
+    }
+}
@@ -0,0 +1,460 @@
+//! Exhaustiveness and redundancy of `match` arms, following Maranget's
+//! "Warnings for pattern matching": a pattern is useful after some rows when
+//! some value matches it but none of the rows, and a match is exhaustive when
+//! the wildcard is not useful after all of its arms.
+use crate::ast::{Pattern, Variant};
+use crate::bigint::BigInt;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Stop looking for missing cases once this many have been found.
+const MISSING_LIMIT: usize = 16;
+
+/// What a pattern requires of the outermost shape of a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    /// `type_name` is empty when the tag belongs to no declared type.
+    Variant {
+        type_name: String,
+        tag: String,
+        arity: usize,
+    },
+    Nil,
+    Cons,
+    Boolean(bool),
+    Integer(BigInt),
+    String(String),
+}
+
+impl Constructor {
+    fn arity(&self) -> usize {
+        match self {
+            Constructor::Variant { arity, .. } => *arity,
+            Constructor::Cons => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// A pattern reduced to constructors and wildcards. Bindings are wildcards
+/// and list patterns are chains of `Cons` ending in `Nil`.
+#[derive(Debug, Clone)]
+pub enum Pat {
+    Wild,
+    Constructor(Constructor, Vec<Pat>),
+}
+
+impl Pat {
+    fn is_compound(&self) -> bool {
+        matches!(self, Pat::Constructor(_, arguments) if !arguments.is_empty())
+            && self.list().is_none()
+    }
+
+    /// The elements of a `Cons` chain ending in `Nil`, a list of known length.
+    fn list(&self) -> Option<Vec<&Pat>> {
+        match self {
+            Pat::Constructor(Constructor::Nil, _) => Some(vec![]),
+            Pat::Constructor(Constructor::Cons, arguments) => {
+                let (head, tail) = (arguments.first()?, arguments.get(1)?);
+                let mut elements = vec![head];
+                elements.extend(tail.list()?);
+                Some(elements)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Shown as the source that would match the same values, as in `Alive _`.
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (constructor, arguments) = match self {
+            Pat::Wild => return write!(f, "_"),
+            Pat::Constructor(constructor, arguments) => (constructor, arguments),
+        };
+        if let (Constructor::Cons, Some(elements)) = (constructor, self.list()) {
+            let elements: Vec<String> =
+                elements.iter().map(|element| element.to_string()).collect();
+            return write!(f, "[{}]", elements.join(", "));
+        }
+        match (constructor, arguments.as_slice()) {
+            (Constructor::Variant { tag, .. }, [payload]) if payload.is_compound() => {
+                write!(f, "{} ({})", tag, payload)
+            }
+            (Constructor::Variant { tag, .. }, [payload]) => write!(f, "{} {}", tag, payload),
+            (Constructor::Variant { tag, .. }, _) => write!(f, "{}", tag),
+            (Constructor::Nil, _) => write!(f, "{{}}"),
+            (Constructor::Cons, [head @ Pat::Constructor(Constructor::Cons, _), tail]) => {
+                write!(f, "({}) :: {}", head, tail)
+            }
+            (Constructor::Cons, [head, tail]) => write!(f, "{} :: {}", head, tail),
+            (Constructor::Cons, _) => write!(f, "_ :: _"),
+            (Constructor::Boolean(value), _) => write!(f, "{}", value),
+            (Constructor::Integer(value), _) => write!(f, "{}", value),
+            (Constructor::String(value), _) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// The tagged unions declared so far, which tell how many variants a value
+/// may take and so whether a set of arms covers all of them.
+#[derive(Debug)]
+pub struct Types {
+    unions: HashMap<String, Vec<Variant>>,
+    /// The type each tag was last declared in.
+    owners: HashMap<String, String>,
+}
+
+/// `Ok`, `Error` and `None` are built in, as the variants of `Result`.
+impl Default for Types {
+    fn default() -> Types {
+        let mut types = Types {
+            unions: HashMap::new(),
+            owners: HashMap::new(),
+        };
+        let variant = |tag: &str, payload: Option<&str>| Variant {
+            tag: tag.to_string(),
+            payload: payload.map(str::to_string),
+        };
+        types.declare(
+            "Result",
+            &[
+                variant("Ok", Some("Any")),
+                variant("Error", Some("Any")),
+                variant("None", None),
+            ],
+        );
+        types
+    }
+}
+
+impl Types {
+    pub fn declare(&mut self, name: &str, variants: &[Variant]) {
+        for variant in variants {
+            self.owners.insert(variant.tag.clone(), name.to_string());
+        }
+        self.unions.insert(name.to_string(), variants.to_vec());
+    }
+
+    fn variant(&self, type_name: &str, tag: &str) -> Option<&Variant> {
+        self.unions
+            .get(type_name)?
+            .iter()
+            .find(|variant| variant.tag == tag)
+    }
+
+    pub fn lower(&self, pattern: &Pattern) -> Pat {
+        match pattern {
+            Pattern::Wildcard | Pattern::Binding(_) => Pat::Wild,
+            Pattern::Integer(value) => {
+                Pat::Constructor(Constructor::Integer(value.clone()), vec![])
+            }
+            Pattern::String(value) => Pat::Constructor(Constructor::String(value.clone()), vec![]),
+            Pattern::Boolean(value) => Pat::Constructor(Constructor::Boolean(*value), vec![]),
+            Pattern::Variant {
+                type_name,
+                tag,
+                payload,
+            } => {
+                let type_name = type_name
+                    .clone()
+                    .or_else(|| self.owners.get(tag).cloned())
+                    .unwrap_or_default();
+                let arity = match self.variant(&type_name, tag) {
+                    Some(variant) => usize::from(variant.payload.is_some()),
+                    None => usize::from(payload.is_some()),
+                };
+                // A variant named without its payload matches any payload.
+                let arguments = match payload {
+                    Some(payload) if arity == 1 => vec![self.lower(payload)],
+                    _ => vec![Pat::Wild; arity],
+                };
+                let constructor = Constructor::Variant {
+                    type_name,
+                    tag: tag.clone(),
+                    arity,
+                };
+                Pat::Constructor(constructor, arguments)
+            }
+            Pattern::Cons(head, tail) => {
+                Pat::Constructor(Constructor::Cons, vec![self.lower(head), self.lower(tail)])
+            }
+            Pattern::List(elements) => elements.iter().rev().fold(
+                Pat::Constructor(Constructor::Nil, vec![]),
+                |tail, element| {
+                    Pat::Constructor(Constructor::Cons, vec![self.lower(element), tail])
+                },
+            ),
+        }
+    }
+
+    /// Every constructor a value of the same type as `constructor` may have,
+    /// or `None` when there are too many to list, as for integers.
+    fn signature(&self, constructor: &Constructor) -> Option<Vec<Constructor>> {
+        match constructor {
+            Constructor::Variant { type_name, .. } => {
+                let variants = self.unions.get(type_name)?;
+                Some(
+                    variants
+                        .iter()
+                        .map(|variant| Constructor::Variant {
+                            type_name: type_name.clone(),
+                            tag: variant.tag.clone(),
+                            arity: usize::from(variant.payload.is_some()),
+                        })
+                        .collect(),
+                )
+            }
+            Constructor::Nil | Constructor::Cons => Some(vec![Constructor::Nil, Constructor::Cons]),
+            Constructor::Boolean(_) => Some(vec![
+                Constructor::Boolean(true),
+                Constructor::Boolean(false),
+            ]),
+            Constructor::Integer(_) | Constructor::String(_) => None,
+        }
+    }
+
+    /// The constructors heading the first column of `rows`, along with all
+    /// the constructors of their type when those can be listed.
+    fn heads(&self, rows: &[Vec<Pat>]) -> (Vec<Constructor>, Option<Vec<Constructor>>) {
+        let mut heads: Vec<Constructor> = vec![];
+        for row in rows {
+            if let Some(Pat::Constructor(constructor, _)) = row.first() {
+                if !heads.contains(constructor) {
+                    heads.push(constructor.clone());
+                }
+            }
+        }
+        let signature = heads.first().and_then(|head| self.signature(head));
+        (heads, signature)
+    }
+
+    /// Whether some value matches `row` but none of `rows`.
+    pub fn useful(&self, rows: &[Vec<Pat>], row: &[Pat]) -> bool {
+        let Some((first, rest)) = row.split_first() else {
+            return rows.is_empty();
+        };
+        match first {
+            Pat::Constructor(constructor, arguments) => {
+                let row = [arguments.as_slice(), rest].concat();
+                self.useful(&specialize(rows, constructor), &row)
+            }
+            Pat::Wild => match self.heads(rows) {
+                (heads, Some(signature))
+                    if signature
+                        .iter()
+                        .all(|constructor| heads.contains(constructor)) =>
+                {
+                    signature.iter().any(|constructor| {
+                        let row = [vec![Pat::Wild; constructor.arity()].as_slice(), rest].concat();
+                        self.useful(&specialize(rows, constructor), &row)
+                    })
+                }
+                _ => self.useful(&default(rows), rest),
+            },
+        }
+    }
+
+    /// Patterns for the values of a single column that match none of `rows`.
+    pub fn missing(&self, rows: &[Vec<Pat>]) -> Vec<Pat> {
+        self.witnesses(rows, 1)
+            .into_iter()
+            .filter_map(|mut witness| witness.pop())
+            .collect()
+    }
+
+    /// Rows of `width` patterns, each matching some values that none of
+    /// `rows` match.
+    fn witnesses(&self, rows: &[Vec<Pat>], width: usize) -> Vec<Vec<Pat>> {
+        if width == 0 {
+            return if rows.is_empty() {
+                vec![vec![]]
+            } else {
+                vec![]
+            };
+        }
+        let mut found = vec![];
+        match self.heads(rows).1 {
+            // Look for what is missing under each constructor in turn.
+            Some(signature) => {
+                for constructor in signature {
+                    let arity = constructor.arity();
+                    let specialized = specialize(rows, &constructor);
+                    for mut witness in self.witnesses(&specialized, arity + width - 1) {
+                        let rest = witness.split_off(arity);
+                        let mut row = vec![Pat::Constructor(constructor.clone(), witness)];
+                        row.extend(rest);
+                        found.push(row);
+                        if found.len() >= MISSING_LIMIT {
+                            return found;
+                        }
+                    }
+                }
+            }
+            // Too many constructors to list: only a wildcard covers them.
+            None => {
+                for witness in self.witnesses(&default(rows), width - 1) {
+                    found.push([vec![Pat::Wild], witness].concat());
+                    if found.len() >= MISSING_LIMIT {
+                        return found;
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+/// The rows that can match a value built with `constructor`, with their
+/// first pattern replaced by the patterns for its arguments.
+fn specialize(rows: &[Vec<Pat>], constructor: &Constructor) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let (first, rest) = row.split_first()?;
+            let arguments = match first {
+                Pat::Wild => vec![Pat::Wild; constructor.arity()],
+                Pat::Constructor(head, arguments) if head == constructor => arguments.clone(),
+                Pat::Constructor(..) => return None,
+            };
+            Some([arguments.as_slice(), rest].concat())
+        })
+        .collect()
+}
+
+/// The rows starting with a wildcard, without it: those that match values
+/// built with a constructor none of the rows mention.
+fn default(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| match row.split_first()? {
+            (Pat::Wild, rest) => Some(rest.to_vec()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(tag: &str, payload: Option<&str>) -> Variant {
+        Variant {
+            tag: tag.to_string(),
+            payload: payload.map(str::to_string),
+        }
+    }
+
+    fn tag(tag: &str, payload: Option<Pattern>) -> Pattern {
+        Pattern::Variant {
+            type_name: None,
+            tag: tag.to_string(),
+            payload: payload.map(Box::new),
+        }
+    }
+
+    fn binding(name: &str) -> Pattern {
+        Pattern::Binding(name.to_string())
+    }
+
+    fn cons(head: Pattern, tail: Pattern) -> Pattern {
+        Pattern::Cons(Box::new(head), Box::new(tail))
+    }
+
+    /// `Cell = Alive Int | Dormant | Dead` and `Wrapper = Wrap Cell | Empty`.
+    fn types() -> Types {
+        let mut types = Types::default();
+        types.declare(
+            "Cell",
+            &[
+                variant("Alive", Some("Int")),
+                variant("Dormant", None),
+                variant("Dead", None),
+            ],
+        );
+        types.declare(
+            "Wrapper",
+            &[variant("Wrap", Some("Cell")), variant("Empty", None)],
+        );
+        types
+    }
+
+    fn rows(types: &Types, patterns: &[Pattern]) -> Vec<Vec<Pat>> {
+        patterns
+            .iter()
+            .map(|pattern| vec![types.lower(pattern)])
+            .collect()
+    }
+
+    fn missing(types: &Types, patterns: &[Pattern]) -> Vec<String> {
+        let rows = rows(types, patterns);
+        types.missing(&rows).iter().map(Pat::to_string).collect()
+    }
+
+    #[test]
+    fn a_missing_variant_is_reported() {
+        let types = types();
+        let arms = [tag("Alive", Some(binding("n"))), tag("Dormant", None)];
+        assert_eq!(missing(&types, &arms), ["Dead"]);
+    }
+
+    #[test]
+    fn missing_payloads_are_reported_inside_their_variant() {
+        let types = types();
+        let arms = [
+            tag("Wrap", Some(tag("Alive", Some(binding("n"))))),
+            tag("Wrap", Some(tag("Dead", None))),
+            tag("Empty", None),
+        ];
+        assert_eq!(missing(&types, &arms), ["Wrap Dormant"]);
+        let arms = [tag("Wrap", Some(tag("Alive", Some(binding("n")))))];
+        assert_eq!(
+            missing(&types, &arms),
+            ["Wrap Dormant", "Wrap Dead", "Empty"]
+        );
+    }
+
+    #[test]
+    fn fixed_length_lists_leave_longer_ones_missing() {
+        let types = types();
+        let arms = [Pattern::List(vec![]), Pattern::List(vec![binding("x")])];
+        assert_eq!(missing(&types, &arms), ["_ :: _ :: _"]);
+        let arms = [
+            Pattern::List(vec![]),
+            Pattern::List(vec![binding("x")]),
+            cons(binding("x"), cons(binding("y"), binding("rest"))),
+        ];
+        assert!(missing(&types, &arms).is_empty());
+    }
+
+    #[test]
+    fn a_fixed_length_list_after_cons_is_unreachable() {
+        let types = types();
+        let rows = rows(&types, &[cons(binding("head"), binding("tail"))]);
+        let pair = Pattern::List(vec![binding("x"), binding("y")]);
+        assert!(!types.useful(&rows, &[types.lower(&pair)]));
+        assert!(types.useful(&rows, &[types.lower(&Pattern::List(vec![]))]));
+    }
+
+    #[test]
+    fn an_arm_after_a_wildcard_is_unreachable() {
+        let types = types();
+        let rows = rows(&types, &[tag("Dead", None), Pattern::Wildcard]);
+        let alive = types.lower(&tag("Alive", Some(binding("n"))));
+        let row = [alive];
+        assert!(!types.useful(&rows, &row));
+        assert!(types.useful(&rows[..1], &row));
+        assert!(types.missing(&rows).is_empty());
+    }
+
+    #[test]
+    fn missing_cases_are_capped() {
+        let mut types = types();
+        let tags: Vec<String> = (0..MISSING_LIMIT + 4)
+            .map(|index| format!("V{}", index))
+            .collect();
+        let variants: Vec<Variant> = tags.iter().map(|tag| variant(tag, None)).collect();
+        types.declare("Many", &variants);
+        let found = missing(&types, &[tag("V0", None)]);
+        assert_eq!(found.len(), MISSING_LIMIT);
+        assert_eq!(found.first().map(String::as_str), Some("V1"));
+    }
+}
@@ -0,0 +1,205 @@
+mod exhaustiveness;
+use crate::ast::*;
+use crate::diagnostics::Diagnostic;
+use exhaustiveness::{Pat, Types};
+
+/// Static checks run after parsing and before evaluation. They only warn:
+/// a program that fails them still runs.
+///
+/// Type declarations are remembered across calls to `check` so that the REPL
+/// can match on a type declared on an earlier line.
+#[derive(Debug, Default)]
+pub struct Checker {
+    types: Types,
+}
+
+/// At most this many missing cases are spelled out in a warning.
+const MISSING_SHOWN: usize = 3;
+
+impl Checker {
+    pub fn new() -> Checker {
+        Checker::default()
+    }
+
+    /// Warn about every `match` in `program` that misses a case or has an arm
+    /// that can never be reached.
+    pub fn check(&mut self, program: &Program) -> Vec<Diagnostic> {
+        // Declarations are gathered first since a function may match on a
+        // type declared further down.
+        walk(program, &mut |node| {
+            if let Node::Statement(Statement {
+                kind: StatementKind::Let(name, expression),
+                ..
+            }) = node
+            {
+                if let ExpressionKind::Type(variants) = &expression.kind {
+                    self.types.declare(name, variants);
+                }
+            }
+        });
+
+        let mut diagnostics = vec![];
+        walk(program, &mut |node| {
+            if let Node::Expression(Expression {
+                kind: ExpressionKind::Match { arms, .. },
+                span,
+            }) = node
+            {
+                diagnostics.extend(self.check_match(arms, *span));
+            }
+        });
+        diagnostics
+    }
+
+    fn check_match(&self, arms: &[Arm], span: crate::span::Span) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut rows: Vec<Vec<Pat>> = vec![];
+        for arm in arms {
+            let row = vec![self.types.lower(&arm.pattern)];
+            if !self.types.useful(&rows, &row) {
+                diagnostics.push(
+                    Diagnostic::warning("unreachable `match` arm", arm.span)
+                        .with_label("already covered by the arms above"),
+                );
+            }
+            rows.push(row);
+        }
+
+        let missing = self.types.missing(&rows);
+        if !missing.is_empty() {
+            let mut cases: Vec<String> = missing
+                .iter()
+                .take(MISSING_SHOWN)
+                .map(|case| format!("`{}`", case))
+                .collect();
+            if missing.len() > MISSING_SHOWN {
+                cases.push(format!("{} more", missing.len() - MISSING_SHOWN));
+            }
+            let cases = match cases.split_last() {
+                Some((last, [])) => last.clone(),
+                Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+                None => unreachable!(),
+            };
+            diagnostics.push(
+                Diagnostic::warning(
+                    format!("non-exhaustive `match`: {} not covered", cases),
+                    span,
+                )
+                .with_label("some values match none of the arms")
+                .with_hint("add arms for the missing cases, or a `| _ -> ...` arm"),
+            );
+        }
+        diagnostics
+    }
+}
+
+enum Node<'a> {
+    Statement(&'a Statement),
+    Expression(&'a Expression),
+}
+
+/// Call `visit` on every statement and expression of `program`, outermost
+/// first.
+fn walk<'a>(program: &'a Program, visit: &mut impl FnMut(Node<'a>)) {
+    for statement in program {
+        visit(Node::Statement(statement));
+        match &statement.kind {
+            StatementKind::Let(_, expression)
+            | StatementKind::Return(expression)
+            | StatementKind::Expression(expression) => walk_expression(expression, visit),
+            StatementKind::Comment(_) => {}
+        }
+    }
+}
+
+fn walk_expression<'a>(expression: &'a Expression, visit: &mut impl FnMut(Node<'a>)) {
+    visit(Node::Expression(expression));
+    match &expression.kind {
+        ExpressionKind::None
+        | ExpressionKind::Identifier(_)
+        | ExpressionKind::Error(None)
+        | ExpressionKind::Unit
+        | ExpressionKind::Type(_)
+        | ExpressionKind::Literal(
+            Literal::Integer(_) | Literal::Float(_) | Literal::String(_) | Literal::Boolean(_),
+        ) => {}
+        ExpressionKind::Literal(Literal::List(elements)) => {
+            for element in elements {
+                walk_expression(element, visit);
+            }
+        }
+        ExpressionKind::Literal(Literal::Map(fields)) => {
+            for (_, value) in fields {
+                walk_expression(value, visit);
+            }
+        }
+        ExpressionKind::Index { collection, index } => {
+            walk_expression(collection, visit);
+            walk_expression(index, visit);
+        }
+        ExpressionKind::Interpolation(parts) => {
+            for part in parts {
+                walk_expression(part, visit);
+            }
+        }
+        ExpressionKind::Update { record, fields } => {
+            walk_expression(record, visit);
+            for (_, value) in fields {
+                walk_expression(value, visit);
+            }
+        }
+        ExpressionKind::Ok(inner)
+        | ExpressionKind::Error(Some(inner))
+        | ExpressionKind::Prefix(_, inner)
+        | ExpressionKind::Postfix(_, inner) => walk_expression(inner, visit),
+        ExpressionKind::Infix(_, left, right) => {
+            walk_expression(left, visit);
+            walk_expression(right, visit);
+        }
+        ExpressionKind::Range {
+            start, end, step, ..
+        } => {
+            walk_expression(start, visit);
+            walk_expression(end, visit);
+            if let Some(step) = step {
+                walk_expression(step, visit);
+            }
+        }
+        ExpressionKind::For {
+            domain,
+            filters,
+            map,
+            ..
+        } => {
+            walk_expression(domain, visit);
+            for filter in filters {
+                walk_expression(filter, visit);
+            }
+            walk_expression(map, visit);
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            walk_expression(scrutinee, visit);
+            for arm in arms {
+                walk_expression(&arm.body, visit);
+            }
+        }
+        ExpressionKind::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            walk_expression(condition, visit);
+            walk(consequence, visit);
+            if let Some(alternative) = alternative {
+                walk(alternative, visit);
+            }
+        }
+        ExpressionKind::Fn { body, .. } => walk(body, visit),
+        ExpressionKind::Call { map, domain } => {
+            walk_expression(map, visit);
+            for argument in domain {
+                walk_expression(argument, visit);
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::span::Span;
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Level::Error => "\x1b[1;31m",
+            Level::Warning => "\x1b[1;33m",
+        }
+    }
+}
+
+/// A message about a span of source, produced by any stage of the toolchain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub span: Span,
+    /// Short text printed next to the carets.
+    pub label: Option<String>,
+    /// Suggestion printed below the snippet.
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(Level::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic::new(Level::Warning, message, span)
+    }
+
+    fn new(level: Level, message: impl Into<String>, span: Span) -> Diagnostic {
+        Diagnostic {
+            level,
+            message: message.into(),
+            span,
+            label: None,
+            hint: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Diagnostic {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Diagnostic {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Whether diagnostics written to stderr should be colored: only when it is a
+/// terminal and `NO_COLOR` is not set.
+pub fn stderr_supports_color() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders diagnostics against the source they refer to, in the style of
+/// `rustc`:
+///
+/// ```text
+/// error: expected `{`, found `=`
+///  --> main.gdl:1:6
+///   |
+/// 1 | if x = 1 { 2 }
+///   |      ^ expected `{`
+///   |
+///   = help: did you mean `==`?
+/// ```
+pub struct Renderer<'a> {
+    filename: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(filename: &'a str, source: &'a str) -> Renderer<'a> {
+        Renderer {
+            filename,
+            source,
+            color: false,
+        }
+    }
+
+    /// Use ANSI escape codes to color the output.
+    pub fn color(mut self, color: bool) -> Renderer<'a> {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let level = diagnostic.level;
+        let span = diagnostic.span;
+        let line = self
+            .source
+            .lines()
+            .nth(span.line.saturating_sub(1))
+            .unwrap_or("");
+        let number = span.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let bar = self.paint(BLUE, "|");
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(level.color(), level.name()),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}",
+            gutter,
+            self.paint(BLUE, "-->"),
+            self.filename,
+            span
+        );
+        let _ = writeln!(out, "{} {}", gutter, bar);
+        let _ = writeln!(out, "{} {} {}", self.paint(BLUE, &number), bar, line);
+
+        // Underline the part of the span that falls on its first line.
+        let column = span.column.saturating_sub(1);
+        let line_start = self.source[..span.start.min(self.source.len())]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let line_end = line_start + line.len();
+        let width = self
+            .source
+            .get(span.start..span.end.min(line_end).max(span.start))
+            .map_or(0, |text| text.chars().count())
+            .max(1);
+        let mut underline = "^".repeat(width);
+        if let Some(label) = &diagnostic.label {
+            underline = format!("{} {}", underline, label);
+        }
+        let _ = writeln!(
+            out,
+            "{} {} {}{}",
+            gutter,
+            bar,
+            " ".repeat(column),
+            self.paint(level.color(), &underline)
+        );
+
+        if let Some(hint) = &diagnostic.hint {
+            let _ = writeln!(out, "{} {}", gutter, bar);
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                gutter,
+                self.paint(BLUE, "="),
+                self.paint(BOLD, &format!("help: {}", hint))
+            );
+        }
+        out
+    }
+}
@@ -0,0 +1,55 @@
+use super::object::Object;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// A lexical scope mapping names to values. Scopes are chained through
+/// `outer`, so a lookup walks outwards until the name is found and a
+/// binding in an inner scope shadows any binding of the same name above it.
+#[derive(Default)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    /// Create a fresh scope nested inside `outer`.
+    pub fn enclose(outer: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            store: HashMap::new(),
+            outer: Some(outer),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .outer
+                .as_ref()
+                .and_then(|outer| outer.borrow().get(name)),
+        }
+    }
+
+    /// Bind `name` in this scope, shadowing any outer binding.
+    pub fn set(&mut self, name: &str, value: Object) {
+        self.store.insert(name.to_string(), value);
+    }
+}
+
+// Closures hold the environment they were defined in, and that environment
+// usually holds the closure, so only the bound names are printed.
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names: Vec<&String> = self.store.keys().collect();
+        names.sort();
+        f.debug_struct("Environment")
+            .field("names", &names)
+            .finish()
+    }
+}
@@ -0,0 +1,87 @@
+use super::object::Object;
+use crate::diagnostics::Diagnostic;
+use crate::span::Span;
+use std::fmt;
+
+/// An error raised while evaluating a program. Evaluation stops at the first
+/// one and it is handed back to the caller of `Evaluator::eval`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    /// An operator or call was given a value of the wrong type.
+    TypeMismatch(String),
+    UnboundIdentifier(String),
+    DivisionByZero,
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// A value of the right type that is outside what the operation accepts.
+    InvalidValue(String),
+    /// A number too large for where it is used, with the limit it broke,
+    /// such as an Int used as an index or the parts of a Rational.
+    Overflow(&'static str),
+    /// A construct that parses but has no runtime meaning yet.
+    Unsupported(String),
+    /// None of the arms of a `match` accepted the value, shown here.
+    NoMatch(String),
+    /// Calls nested deeper than the evaluator allows, given here. Calls in
+    /// tail position replace the caller and do not count.
+    RecursionLimit(usize),
+    /// Not an error: `return` handing a value back from the enclosing
+    /// function. It unwinds through the expressions around it like
+    /// `EarlyReturn`, and at the top level ends the program with the value.
+    Return(Box<Object>),
+    /// `?` handing an `Error` or `None` back from the enclosing function. It
+    /// unwinds like an error through the expressions around it and becomes
+    /// the function's result in `Evaluator::apply_function`; only outside of
+    /// any function is it reported as an error.
+    EarlyReturn(Box<Object>),
+}
+
+impl RuntimeError {
+    /// Describe this error as raised by the expression at `span`.
+    pub fn diagnostic(&self, span: Span) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), span);
+        match self {
+            RuntimeError::UnboundIdentifier(name) => diagnostic
+                .with_label("not found in this scope")
+                .with_hint(format!("bind it first with `let {} = ...;`", name)),
+            RuntimeError::DivisionByZero => diagnostic.with_label("the divisor is zero"),
+            RuntimeError::Overflow(_) => diagnostic.with_label("this number is too large"),
+            RuntimeError::NoMatch(_) => diagnostic
+                .with_label("no arm matches this value")
+                .with_hint("add a `| _ -> ...` arm to handle every other value"),
+            RuntimeError::RecursionLimit(_) => diagnostic
+                .with_label("this call is nested too deeply")
+                .with_hint("make the recursive call the last thing the function does"),
+            RuntimeError::EarlyReturn(_) => diagnostic
+                .with_label("no function to return this from")
+                .with_hint("handle the failure with `match` instead"),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::TypeMismatch(message) => write!(f, "type mismatch: {}", message),
+            RuntimeError::UnboundIdentifier(name) => write!(f, "unbound identifier `{}`", name),
+            RuntimeError::DivisionByZero => write!(f, "division by zero"),
+            RuntimeError::ArityMismatch { expected, found } => {
+                write!(f, "expected {} argument(s), found {}", expected, found)
+            }
+            RuntimeError::InvalidValue(message) => write!(f, "invalid value: {}", message),
+            RuntimeError::Overflow(limit) => write!(f, "number too large: {}", limit),
+            RuntimeError::Unsupported(what) => write!(f, "{} cannot be evaluated yet", what),
+            RuntimeError::NoMatch(value) => write!(f, "no pattern matched `{}`", value),
+            RuntimeError::RecursionLimit(depth) => {
+                write!(f, "maximum recursion depth of {} exceeded", depth)
+            }
+            RuntimeError::Return(value) => write!(f, "`return {}` outside of a function", value),
+            RuntimeError::EarlyReturn(value) => write!(f, "unhandled `{}` from `?`", value),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
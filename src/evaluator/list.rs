@@ -0,0 +1,51 @@
+use super::object::Object;
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// The elements of a list value, shared by every copy of it so that binding,
+/// passing and reading a list never copies its elements. A list may be a
+/// view of another one's elements from `start` on, which is how `tail` in
+/// `head :: tail` is taken without copying.
+#[derive(Debug, Clone)]
+pub struct List {
+    elements: Rc<[Object]>,
+    start: usize,
+}
+
+impl List {
+    /// The first element and a view of the rest, or `None` when empty.
+    pub fn split_first(&self) -> Option<(&Object, List)> {
+        let first = self.first()?;
+        let rest = List {
+            elements: Rc::clone(&self.elements),
+            start: self.start + 1,
+        };
+        Some((first, rest))
+    }
+}
+
+impl Deref for List {
+    type Target = [Object];
+
+    fn deref(&self) -> &[Object] {
+        &self.elements[self.start..]
+    }
+}
+
+impl From<Vec<Object>> for List {
+    fn from(elements: Vec<Object>) -> List {
+        List {
+            elements: elements.into(),
+            start: 0,
+        }
+    }
+}
+
+impl FromIterator<Object> for List {
+    fn from_iter<I: IntoIterator<Item = Object>>(iter: I) -> List {
+        List {
+            elements: iter.into_iter().collect(),
+            start: 0,
+        }
+    }
+}
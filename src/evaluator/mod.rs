@@ -1,12 +1,70 @@
+pub mod environment;
+pub mod error;
+pub mod list;
 pub mod object;
+pub mod range;
+pub mod rational;
 use super::ast::*;
+use super::bigint::BigInt;
+use super::lexer::Token;
+use super::span::{Span, Spanned};
+use environment::*;
+use error::*;
+use list::List;
 use object::*;
+use range::Range;
+use rational::Rational;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
 
-pub struct Evaluator {}
+/// How many calls may be nested before evaluation stops with an error.
+pub const MAX_DEPTH: usize = 10_000;
+
+// Where Ints must stay small, as told by `RuntimeError::Overflow`.
+const INDEX_LIMIT: &str = "an index must fit in 64 bits";
+const RANGE_LIMIT: &str = "range bounds must fit in 64 bits";
+const EXPONENT_LIMIT: &str = "an exponent must fit in 32 bits";
+
+pub struct Evaluator {
+    env: Rc<RefCell<Environment>>,
+    span: Span,
+    /// The number of calls currently being evaluated.
+    depth: usize,
+    max_depth: usize,
+}
+
+/// The result of evaluating an expression in tail position: a value, or a
+/// call left for `apply_function` to make in place of the current one.
+enum Tail {
+    Value(Object),
+    Call {
+        function: Object,
+        arguments: Vec<Object>,
+        span: Span,
+    },
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Evaluator {
     pub fn new() -> Evaluator {
-        Evaluator {}
+        Evaluator {
+            env: Rc::new(RefCell::new(Environment::new())),
+            span: Span::default(),
+            depth: 0,
+            max_depth: MAX_DEPTH,
+        }
+    }
+
+    /// Allow at most `depth` nested calls instead of `MAX_DEPTH`.
+    pub fn max_depth(mut self, depth: usize) -> Evaluator {
+        self.max_depth = depth;
+        self
     }
 
     fn is_truthy(object: Object) -> bool {
@@ -18,132 +76,1355 @@ impl Evaluator {
         }
     }
 
-    pub fn eval(&mut self, program: &Program) -> Option<Object> {
-        let mut result: Option<Object> = None;
+    /// Evaluate every statement of `program` and return the value of the last
+    /// one. Statements without a value, such as `let`, evaluate to `Null`.
+    /// An error carries the span of the expression that raised it, and a `?`
+    /// outside of any function is an error at that `?`. A `return` outside of
+    /// any function ends the program with its value.
+    pub fn eval(&mut self, program: &Program) -> Result<Object, Spanned<RuntimeError>> {
+        let mut result = Object::Null;
         for statement in program {
+            self.span = statement.span;
             match self.eval_statement(statement) {
-                Some(Object::Return(value)) => return Some(*value),
-                obj => result = obj,
-                // _ => unreachable!("Only integer wors"),
+                Ok(obj) => result = obj,
+                Err(RuntimeError::Return(value)) => return Ok(*value),
+                Err(error) => return Err(Spanned::new(error, self.span)),
             }
         }
-        result
+        Ok(result)
     }
 
-    pub fn eval_statement(&mut self, statement: &Statement) -> Option<Object> {
-        match statement {
-            Statement::Expression(expression) => self.eval_expression(expression),
-            Statement::Return(expression) => match self.eval_expression(expression) {
-                Some(value) => Some(Object::Return(Box::new(value))),
-                None => return None,
-            },
-            _ => None,
+    pub fn eval_statement(&mut self, statement: &Statement) -> Result<Object, RuntimeError> {
+        match &statement.kind {
+            StatementKind::Expression(expression) => self.eval_expression(expression),
+            StatementKind::Return(expression) => {
+                let value = self.eval_expression(expression)?;
+                Err(RuntimeError::Return(Box::new(value)))
+            }
+            StatementKind::Let(name, expression) => {
+                if let ExpressionKind::Type(variants) = &expression.kind {
+                    let span = expression.span;
+                    return self
+                        .eval_type(name, variants)
+                        .inspect_err(|_| self.span = span);
+                }
+                let value = self.eval_expression(expression)?;
+                self.env.borrow_mut().set(name, value);
+                Ok(Object::Null)
+            }
+            StatementKind::Comment(_) => Ok(Object::Null),
         }
     }
 
-    pub fn eval_expression(&mut self, expression: &Expression) -> Option<Object> {
+    /// Evaluate `expression`. When it fails the span of the innermost
+    /// expression that failed is left in `self.span` for `eval` to report.
+    pub fn eval_expression(&mut self, expression: &Expression) -> Result<Object, RuntimeError> {
+        let outer = std::mem::replace(&mut self.span, expression.span);
+        let value = self.eval_expression_kind(&expression.kind)?;
+        self.span = outer;
+        Ok(value)
+    }
+
+    fn eval_expression_kind(
+        &mut self,
+        expression: &ExpressionKind,
+    ) -> Result<Object, RuntimeError> {
         match expression {
-            Expression::Literal(literal) => Some(self.eval_literal(literal)),
-            Expression::Prefix(prefix, expression) => self
-                .eval_expression(&expression)
-                .map(|right| self.eval_prefix(prefix, right)),
-            Expression::Infix(infix, left_expression, right_expression) => {
-                let left = self.eval_expression(left_expression);
-                let right = self.eval_expression(right_expression);
-                if left.is_some() && right.is_some() {
-                    Some(self.eval_infix(infix, left.unwrap(), right.unwrap()))
-                } else {
-                    None
+            ExpressionKind::Literal(literal) => self.eval_literal(literal),
+            ExpressionKind::Identifier(identifier) => self.eval_identifier(identifier),
+            ExpressionKind::Prefix(prefix, expression) => {
+                let right = self.eval_expression(expression)?;
+                self.eval_prefix(prefix, right)
+            }
+            ExpressionKind::Infix(Infix::Pipe, left, right) => self.eval_pipe(left, right),
+            ExpressionKind::Infix(infix, left_expression, right_expression) => {
+                let left = self.eval_expression(left_expression)?;
+                let right = self.eval_expression(right_expression)?;
+                self.eval_infix(infix, left, right)
+            }
+            ExpressionKind::Postfix(postfix, expression) => {
+                let left = self.eval_expression(expression)?;
+                self.eval_postfix(postfix, left)
+            }
+            ExpressionKind::Update { record, fields } => self.eval_update(record, fields),
+            ExpressionKind::Index { collection, index } => {
+                let collection = self.eval_expression(collection)?;
+                match self.eval_expression(index)? {
+                    Object::Integer(position) => Self::eval_index(position.into(), collection),
+                    Object::Range(range) => Self::eval_slice(range, collection),
+                    Object::BigInt(_) => {
+                        self.span = index.span;
+                        Err(RuntimeError::Overflow(INDEX_LIMIT))
+                    }
+                    other => {
+                        self.span = index.span;
+                        Err(RuntimeError::TypeMismatch(format!(
+                            "cannot index by {}",
+                            other.type_name()
+                        )))
+                    }
+                }
+            }
+            ExpressionKind::Interpolation(parts) => {
+                let mut text = String::new();
+                for part in parts {
+                    text.push_str(&self.eval_expression(part)?.to_string());
                 }
+                Ok(Object::String(text))
             }
-            Expression::If {
+            ExpressionKind::Match { scrutinee, arms } => self.eval_match(scrutinee, arms),
+            ExpressionKind::If {
                 condition,
                 consequence,
                 alternative,
             } => self.eval_if(condition, consequence, alternative),
-            _ => unreachable!("Only Literal(int,bool,string) works"),
+            ExpressionKind::Fn { parameter, body } => Ok(Object::Function {
+                parameters: parameter
+                    .iter()
+                    .filter(|p| **p != Token::Unit)
+                    .cloned()
+                    .collect(),
+                body: Rc::new(body.clone()),
+                env: Rc::clone(&self.env),
+            }),
+            ExpressionKind::Call { map, domain } => self.eval_call(map, domain),
+            ExpressionKind::None => Ok(Object::None),
+            ExpressionKind::Unit => Err(RuntimeError::Unsupported("`()`".to_string())),
+            ExpressionKind::Ok(value) => Ok(Object::Ok(Box::new(self.eval_expression(value)?))),
+            ExpressionKind::Error(None) => Ok(Object::Error(Box::new(Object::Null))),
+            ExpressionKind::Error(Some(message)) => {
+                Ok(Object::Error(Box::new(self.eval_expression(message)?)))
+            }
+            ExpressionKind::Type(_) => Err(RuntimeError::Unsupported(
+                "a `type` that is not bound by `let`".to_string(),
+            )),
+            ExpressionKind::For {
+                binder,
+                domain,
+                filters,
+                map,
+            } => self.eval_for(binder, domain, filters, map),
+            ExpressionKind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => self.eval_range(start, end, step.as_deref(), *inclusive),
+        }
+    }
+
+    /// Bind the tagged union `name` along with each of its variants, so both
+    /// `Cell.Dormant` and plain `Dormant` refer to the same value.
+    pub fn eval_type(&mut self, name: &str, variants: &[Variant]) -> Result<Object, RuntimeError> {
+        let mut tags = BTreeSet::new();
+        for variant in variants {
+            if !tags.insert(&variant.tag) {
+                return Err(RuntimeError::InvalidValue(format!(
+                    "variant `{}` is declared more than once in `{}`",
+                    variant.tag, name
+                )));
+            }
+        }
+        let mut env = self.env.borrow_mut();
+        for variant in variants {
+            env.set(&variant.tag, Self::variant(name, variant));
+        }
+        env.set(
+            name,
+            Object::Type {
+                name: name.to_string(),
+                variants: variants.to_vec(),
+            },
+        );
+        Ok(Object::Null)
+    }
+
+    /// The value a variant name refers to: the variant itself when it carries
+    /// nothing, or else the constructor that builds it from its payload.
+    fn variant(type_name: &str, variant: &Variant) -> Object {
+        match &variant.payload {
+            None => Object::Variant {
+                type_name: type_name.to_string(),
+                tag: variant.tag.clone(),
+                payload: None,
+            },
+            Some(payload) => Object::Constructor {
+                type_name: type_name.to_string(),
+                tag: variant.tag.clone(),
+                payload: payload.clone(),
+            },
         }
     }
+
+    pub fn eval_identifier(&mut self, identifier: &Identifier) -> Result<Object, RuntimeError> {
+        match identifier {
+            Token::Identifier(name) => self
+                .env
+                .borrow()
+                .get(name)
+                .ok_or_else(|| RuntimeError::UnboundIdentifier(name.clone())),
+            other => Err(RuntimeError::UnboundIdentifier(format!("{:?}", other))),
+        }
+    }
+
+    pub fn eval_call(
+        &mut self,
+        map: &Expression,
+        domain: &[Expression],
+    ) -> Result<Object, RuntimeError> {
+        let function = self.eval_expression(map)?;
+        let arguments = self.eval_arguments(domain)?;
+        self.apply_function(function, arguments)
+    }
+
+    fn eval_arguments(&mut self, domain: &[Expression]) -> Result<Vec<Object>, RuntimeError> {
+        let mut arguments = vec![];
+        // `f()` is parsed as a call with a single unit argument.
+        for argument in domain
+            .iter()
+            .filter(|a| !matches!(a.kind, ExpressionKind::Unit))
+        {
+            arguments.push(self.eval_expression(argument)?);
+        }
+        Ok(arguments)
+    }
+
+    /// `x |> f` calls `f` with `x`, and `x |> f(a, b)` calls `f(a, b, x)`.
+    pub fn eval_pipe(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<Object, RuntimeError> {
+        let (function, arguments) = self.pipe_call(left, right)?;
+        self.apply_function(function, arguments)
+            .inspect_err(|_| self.span = right.span)
+    }
+
+    /// The function a pipe calls and the arguments it calls it with.
+    fn pipe_call(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+    ) -> Result<(Object, Vec<Object>), RuntimeError> {
+        let value = self.eval_expression(left)?;
+        let (function, mut arguments) = match &right.kind {
+            ExpressionKind::Call { map, domain } => {
+                let function = self.eval_expression(map)?;
+                (function, self.eval_arguments(domain)?)
+            }
+            _ => (self.eval_expression(right)?, vec![]),
+        };
+        arguments.push(value);
+        Ok((function, arguments))
+    }
+
+    /// Call `function` with `arguments`, binding its parameters in a new scope
+    /// nested inside the environment the function was defined in.
+    ///
+    /// Functions are curried: given fewer arguments than parameters the call
+    /// returns a function awaiting the rest, and given more it applies the
+    /// result to the ones left over.
+    ///
+    /// A call in tail position, such as the last expression of the body or of
+    /// a branch of a trailing `if`, replaces the current call rather than
+    /// nesting inside it, so tail recursion runs in constant stack. Other
+    /// calls count towards the maximum depth.
+    pub fn apply_function(
+        &mut self,
+        function: Object,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        if self.depth >= self.max_depth {
+            return Err(RuntimeError::RecursionLimit(self.max_depth));
+        }
+        self.depth += 1;
+        let result = self.trampoline(function, arguments);
+        self.depth -= 1;
+        result
+    }
+
+    /// Make a call and then every call left in tail position, one at a time.
+    fn trampoline(
+        &mut self,
+        mut function: Object,
+        mut arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        loop {
+            match self.enter(function, arguments) {
+                Err(RuntimeError::Return(value) | RuntimeError::EarlyReturn(value)) => {
+                    return Ok(*value)
+                }
+                Ok(Tail::Value(value)) => return Ok(value),
+                Ok(Tail::Call {
+                    function: next,
+                    arguments: rest,
+                    span,
+                }) => {
+                    self.span = span;
+                    function = next;
+                    arguments = rest;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Bind `arguments` and evaluate the body of `function` up to the call
+    /// in tail position, if any.
+    fn enter(
+        &mut self,
+        function: Object,
+        mut arguments: Vec<Object>,
+    ) -> Result<Tail, RuntimeError> {
+        let arity = match &function {
+            Object::Function { parameters, .. } => parameters.len(),
+            Object::Constructor { .. } => 1,
+            other => {
+                return Err(RuntimeError::TypeMismatch(format!(
+                    "{} is not a function",
+                    other.type_name()
+                )))
+            }
+        };
+        if arguments.len() > arity {
+            let rest = arguments.split_off(arity);
+            return match self.apply_function(function, arguments)? {
+                result @ (Object::Function { .. } | Object::Constructor { .. }) => Ok(Tail::Call {
+                    function: result,
+                    arguments: rest,
+                    span: self.span,
+                }),
+                _ => Err(RuntimeError::ArityMismatch {
+                    expected: arity,
+                    found: arity + rest.len(),
+                }),
+            };
+        }
+
+        let (parameters, body, env) = match function {
+            Object::Function {
+                parameters,
+                body,
+                env,
+            } => (parameters, body, env),
+            Object::Constructor {
+                type_name,
+                tag,
+                payload,
+            } => return Self::construct(type_name, tag, &payload, arguments).map(Tail::Value),
+            _ => unreachable!(),
+        };
+        if arguments.is_empty() && arity > 0 {
+            return Err(RuntimeError::ArityMismatch {
+                expected: arity,
+                found: 0,
+            });
+        }
+
+        let mut scope = Environment::enclose(env);
+        let bound = arguments.len();
+        for (parameter, argument) in parameters.iter().zip(arguments) {
+            if let Token::Identifier(name) = parameter {
+                scope.set(name, argument);
+            }
+        }
+        if bound < arity {
+            return Ok(Tail::Value(Object::Function {
+                parameters: parameters[bound..].to_vec(),
+                body,
+                env: Rc::new(RefCell::new(scope)),
+            }));
+        }
+
+        let caller = std::mem::replace(&mut self.env, Rc::new(RefCell::new(scope)));
+        let result = self.eval_tail_statements(&body);
+        self.env = caller;
+        result
+    }
+
+    /// Evaluate `program` as the end of a function body: the last expression
+    /// and any `return` are in tail position.
+    fn eval_tail_statements(&mut self, program: &Program) -> Result<Tail, RuntimeError> {
+        for (index, statement) in program.iter().enumerate() {
+            match &statement.kind {
+                StatementKind::Return(expression) => return self.eval_tail_expression(expression),
+                StatementKind::Expression(expression) if index + 1 == program.len() => {
+                    return self.eval_tail_expression(expression)
+                }
+                _ => {
+                    self.eval_statement(statement)?;
+                }
+            }
+        }
+        Ok(Tail::Value(Object::Null))
+    }
+
+    fn eval_tail_block(&mut self, program: &Program) -> Result<Tail, RuntimeError> {
+        let outer = Rc::clone(&self.env);
+        self.env = Rc::new(RefCell::new(Environment::enclose(Rc::clone(&outer))));
+        let result = self.eval_tail_statements(program);
+        self.env = outer;
+        result
+    }
+
+    /// Evaluate `expression` in tail position, leaving a call it ends in to
+    /// the caller. Calls end an `if` or `match` when they end its branches.
+    fn eval_tail_expression(&mut self, expression: &Expression) -> Result<Tail, RuntimeError> {
+        let outer = std::mem::replace(&mut self.span, expression.span);
+        let tail = match &expression.kind {
+            ExpressionKind::Call { map, domain } => Tail::Call {
+                function: self.eval_expression(map)?,
+                arguments: self.eval_arguments(domain)?,
+                span: expression.span,
+            },
+            ExpressionKind::Infix(Infix::Pipe, left, right) => {
+                let (function, arguments) = self.pipe_call(left, right)?;
+                Tail::Call {
+                    function,
+                    arguments,
+                    span: right.span,
+                }
+            }
+            ExpressionKind::If {
+                condition,
+                consequence,
+                alternative,
+            } => match self.branch(condition, consequence, alternative)? {
+                Some(block) => self.eval_tail_block(block)?,
+                None => Tail::Value(Object::Null),
+            },
+            ExpressionKind::Match { scrutinee, arms } => {
+                let (arm, scope) = self.select_arm(scrutinee, arms)?;
+                let outer = std::mem::replace(&mut self.env, Rc::new(RefCell::new(scope)));
+                let result = self.eval_tail_expression(&arm.body);
+                self.env = outer;
+                result?
+            }
+            kind => Tail::Value(self.eval_expression_kind(kind)?),
+        };
+        self.span = outer;
+        Ok(tail)
+    }
+
+    /// Build the variant `tag` of `type_name` around its single argument,
+    /// which must have the type the variant was declared with.
+    fn construct(
+        type_name: String,
+        tag: String,
+        payload: &str,
+        arguments: Vec<Object>,
+    ) -> Result<Object, RuntimeError> {
+        let [argument]: [Object; 1] =
+            arguments
+                .try_into()
+                .map_err(|arguments: Vec<Object>| RuntimeError::ArityMismatch {
+                    expected: 1,
+                    found: arguments.len(),
+                })?;
+        if !argument.has_type(payload) {
+            return Err(RuntimeError::TypeMismatch(format!(
+                "`{}.{}` expects {}, found {}",
+                type_name,
+                tag,
+                payload,
+                argument.type_name()
+            )));
+        }
+        Ok(Object::Variant {
+            type_name,
+            tag,
+            payload: Some(Box::new(argument)),
+        })
+    }
+
+    /// Evaluate a set-builder expression: `map` is evaluated once for every
+    /// element of `domain` that satisfies all of `filters`, with the element
+    /// bound to `binder` in a scope of its own. The results form a set when
+    /// `domain` is a set and a list otherwise.
+    pub fn eval_for(
+        &mut self,
+        binder: &str,
+        domain: &Expression,
+        filters: &[Expression],
+        map: &Expression,
+    ) -> Result<Object, RuntimeError> {
+        let span = domain.span;
+        let domain = self.eval_expression(domain)?;
+        let builds_set = matches!(domain, Object::Set(_));
+        let elements = Self::iterate(domain).inspect_err(|_| self.span = span)?;
+        let outer = Rc::clone(&self.env);
+        let mut results = vec![];
+        for element in elements {
+            let mut scope = Environment::enclose(Rc::clone(&outer));
+            scope.set(binder, element);
+            self.env = Rc::new(RefCell::new(scope));
+            let result = self.eval_for_element(filters, map);
+            self.env = Rc::clone(&outer);
+            if let Some(value) = result? {
+                results.push(value);
+            }
+        }
+        if builds_set {
+            Object::set(results)
+        } else {
+            Ok(Object::List(List::from(results)))
+        }
+    }
+
+    fn eval_for_element(
+        &mut self,
+        filters: &[Expression],
+        map: &Expression,
+    ) -> Result<Option<Object>, RuntimeError> {
+        for filter in filters {
+            if !Self::is_truthy(self.eval_expression(filter)?) {
+                return Ok(None);
+            }
+        }
+        self.eval_expression(map).map(Some)
+    }
+
+    pub fn eval_range(
+        &mut self,
+        start: &Expression,
+        end: &Expression,
+        step: Option<&Expression>,
+        inclusive: bool,
+    ) -> Result<Object, RuntimeError> {
+        let start = self.eval_range_bound(start)?;
+        let end = self.eval_range_bound(end)?;
+        let stride = match step {
+            Some(step) => match self.eval_range_bound(step)? {
+                stride if stride > 0 => stride,
+                stride => {
+                    self.span = step.span;
+                    return Err(RuntimeError::InvalidValue(format!(
+                        "range step must be positive, found {}",
+                        stride
+                    )));
+                }
+            },
+            None => 1,
+        };
+        Ok(Object::Range(Range::new(start, end, stride, inclusive)))
+    }
+
+    fn eval_range_bound(&mut self, bound: &Expression) -> Result<i64, RuntimeError> {
+        match self.eval_expression(bound)? {
+            Object::Integer(value) => Ok(value),
+            Object::BigInt(_) => {
+                self.span = bound.span;
+                Err(RuntimeError::Overflow(RANGE_LIMIT))
+            }
+            other => {
+                self.span = bound.span;
+                Err(RuntimeError::TypeMismatch(format!(
+                    "range bounds must be Int, found {}",
+                    other.type_name()
+                )))
+            }
+        }
+    }
+
+    /// The elements of a value that can be iterated over by `for`.
+    fn iterate(object: Object) -> Result<Box<dyn Iterator<Item = Object>>, RuntimeError> {
+        match object {
+            Object::List(elements) => Ok(Box::new(
+                (0..elements.len()).map(move |index| elements[index].clone()),
+            )),
+            Object::Range(range) => Ok(Box::new(range.iter().map(Object::Integer))),
+            Object::Set(elements) => Ok(Box::new(Rc::unwrap_or_clone(elements).into_iter())),
+            other => Err(RuntimeError::TypeMismatch(format!(
+                "cannot iterate over {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    /// Evaluate the body of the first arm whose pattern accepts the value of
+    /// `scrutinee`, in a scope holding the names the pattern binds.
+    pub fn eval_match(
+        &mut self,
+        scrutinee: &Expression,
+        arms: &[Arm],
+    ) -> Result<Object, RuntimeError> {
+        let (arm, scope) = self.select_arm(scrutinee, arms)?;
+        let outer = std::mem::replace(&mut self.env, Rc::new(RefCell::new(scope)));
+        let result = self.eval_expression(&arm.body);
+        self.env = outer;
+        result
+    }
+
+    /// The first arm accepting the value of `scrutinee`, with a scope nested
+    /// in the current one that holds the names its pattern binds.
+    fn select_arm<'a>(
+        &mut self,
+        scrutinee: &Expression,
+        arms: &'a [Arm],
+    ) -> Result<(&'a Arm, Environment), RuntimeError> {
+        let value = self.eval_expression(scrutinee)?;
+        for arm in arms {
+            let mut bindings = vec![];
+            if !Self::match_pattern(&arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+            let mut scope = Environment::enclose(Rc::clone(&self.env));
+            for (name, value) in bindings {
+                scope.set(&name, value);
+            }
+            return Ok((arm, scope));
+        }
+        self.span = scrutinee.span;
+        Err(RuntimeError::NoMatch(value.to_string()))
+    }
+
+    /// Whether `value` has the shape of `pattern`, collecting the names the
+    /// pattern binds into `bindings`.
+    fn match_pattern(
+        pattern: &Pattern,
+        value: &Object,
+        bindings: &mut Vec<(String, Object)>,
+    ) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Binding(name), value) => {
+                bindings.push((name.clone(), value.clone()));
+                true
+            }
+            (Pattern::Integer(expected), Object::Integer(value)) => {
+                expected.to_i64() == Some(*value)
+            }
+            (Pattern::Integer(expected), Object::BigInt(value)) => expected == value,
+            (Pattern::String(expected), Object::String(value)) => expected == value,
+            (Pattern::Boolean(expected), Object::Boolean(value)) => expected == value,
+            (
+                Pattern::Variant {
+                    type_name,
+                    tag,
+                    payload,
+                },
+                value,
+            ) => {
+                let Some((value_type, value_tag, value_payload)) = value.as_variant() else {
+                    return false;
+                };
+                tag == value_tag
+                    && type_name.as_ref().is_none_or(|name| name == value_type)
+                    && match (payload, value_payload) {
+                        // A variant named without its payload matches any payload.
+                        (None, _) => true,
+                        (Some(pattern), Some(value)) => {
+                            Self::match_pattern(pattern, value, bindings)
+                        }
+                        (Some(_), None) => false,
+                    }
+            }
+            (Pattern::List(patterns), Object::List(elements)) => {
+                patterns.len() == elements.len()
+                    && patterns
+                        .iter()
+                        .zip(elements.iter())
+                        .all(|(pattern, element)| Self::match_pattern(pattern, element, bindings))
+            }
+            // Ranges are matched element by element, never built.
+            (Pattern::List(patterns), Object::Range(range)) => {
+                patterns.len() as u128 == range.len()
+                    && patterns.iter().enumerate().all(|(index, pattern)| {
+                        let element = range.get(index as u128).map(Object::Integer);
+                        element
+                            .is_some_and(|element| Self::match_pattern(pattern, &element, bindings))
+                    })
+            }
+            (Pattern::Cons(head, tail), Object::List(elements)) => match elements.split_first() {
+                Some((first, rest)) => {
+                    Self::match_pattern(head, first, bindings)
+                        && Self::match_pattern(tail, &Object::List(rest), bindings)
+                }
+                None => false,
+            },
+            (Pattern::Cons(head, tail), Object::Range(range)) => match range.get(0) {
+                Some(first) => {
+                    // The rest of a progression is the one starting a step later.
+                    let rest = match range.get(1) {
+                        Some(second) => Object::Range(Range {
+                            start: second,
+                            ..*range
+                        }),
+                        None => Object::List(List::from(vec![])),
+                    };
+                    Self::match_pattern(head, &Object::Integer(first), bindings)
+                        && Self::match_pattern(tail, &rest, bindings)
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
     pub fn eval_if(
         &mut self,
         condition: &Expression,
         consequence: &Program,
         alternative: &Option<Program>,
-    ) -> Option<Object> {
-        let cond = match self.eval_expression(condition) {
-            Some(cond) => cond,
-            None => return None,
-        };
+    ) -> Result<Object, RuntimeError> {
+        match self.branch(condition, consequence, alternative)? {
+            Some(block) => self.eval_block(block),
+            None => Ok(Object::Null),
+        }
+    }
+
+    /// The block an `if` runs, or `None` when the condition fails and there
+    /// is no `else`.
+    fn branch<'a>(
+        &mut self,
+        condition: &Expression,
+        consequence: &'a Program,
+        alternative: &'a Option<Program>,
+    ) -> Result<Option<&'a Program>, RuntimeError> {
+        let cond = self.eval_expression(condition)?;
 
         if Self::is_truthy(cond) {
-            return self.eval_block(consequence);
-        } else if let Some(alt) = alternative {
-            self.eval_block(alt)
+            Ok(Some(consequence))
         } else {
-            None
+            Ok(alternative.as_ref())
         }
     }
 
-    pub fn eval_block(&mut self, program: &Program) -> Option<Object> {
-        let mut result = None;
+    /// Evaluate `program` in a fresh scope nested inside the current one, so
+    /// bindings made by the block are dropped once it finishes.
+    pub fn eval_block(&mut self, program: &Program) -> Result<Object, RuntimeError> {
+        let outer = Rc::clone(&self.env);
+        self.env = Rc::new(RefCell::new(Environment::enclose(Rc::clone(&outer))));
+        let result = self.eval_statements(program);
+        self.env = outer;
+        result
+    }
+
+    fn eval_statements(&mut self, program: &Program) -> Result<Object, RuntimeError> {
+        let mut result = Object::Null;
         for statement in program {
-            match self.eval_statement(statement) {
-                Some(Object::Return(value)) => return Some(Object::Return(value)),
-                obj => result = obj,
+            result = self.eval_statement(statement)?;
+        }
+        Ok(result)
+    }
+
+    pub fn eval_infix(
+        &mut self,
+        infix: &Infix,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, RuntimeError> {
+        match (&left, &right) {
+            (_, Object::Function { .. }) | (Object::Function { .. }, _) => {
+                Err(Self::infix_mismatch(infix, &left, &right))
+            }
+            _ if (*infix == Infix::Equal || *infix == Infix::NotEqual)
+                && !(left.is_hashable() && right.is_hashable()) =>
+            {
+                Err(RuntimeError::TypeMismatch(format!(
+                    "cannot apply `{}` to a value holding a Function",
+                    infix
+                )))
+            }
+            (_, Object::List(_) | Object::Range(_)) | (Object::List(_) | Object::Range(_), _)
+                if *infix == Infix::Cons || *infix == Infix::Concat =>
+            {
+                self.eval_infix_list(infix, left, right)
+            }
+            (_, Object::Set(_) | Object::List(_) | Object::Range(_) | Object::Map(_))
+                if *infix == Infix::Member =>
+            {
+                self.eval_member(left, right)
+            }
+            (Object::Set(left_set), Object::Set(right_set)) => {
+                self.eval_infix_set(infix, left_set, right_set)
+            }
+            (Object::List(_) | Object::Range(_), Object::List(_) | Object::Range(_))
+            | (Object::Map(_), Object::Map(_))
+                if *infix == Infix::Equal || *infix == Infix::NotEqual =>
+            {
+                Ok(Object::Boolean((left == right) == (*infix == Infix::Equal)))
+            }
+            _ if (*infix == Infix::Equal || *infix == Infix::NotEqual)
+                && left.as_variant().is_some()
+                && right.as_variant().is_some() =>
+            {
+                Ok(Object::Boolean((left == right) == (*infix == Infix::Equal)))
+            }
+            // Comparing a Float uses the exact order that sets and lists are
+            // kept in, so that `x == y` agrees with `[x] == [y]`.
+            (Object::Float(_), _) | (_, Object::Float(_))
+                if infix.is_comparison() && left.is_number() && right.is_number() =>
+            {
+                Self::eval_comparison(infix, &left, &right)
+            }
+            (Object::Integer(left_int), Object::Integer(right_int)) => {
+                self.eval_infix_int(infix, *left_int, *right_int)
+            }
+            (Object::Float(left_float), Object::Float(right_float)) => {
+                self.eval_infix_float(infix, *left_float, *right_float)
+            }
+            (Object::Integer(left_int), Object::Float(right_float)) => {
+                self.eval_infix_float(infix, *left_int as f64, *right_float)
             }
+            (Object::Float(left_float), Object::Integer(right_int)) => {
+                self.eval_infix_float(infix, *left_float, *right_int as f64)
+            }
+            (Object::BigInt(left_big), Object::BigInt(right_big)) => {
+                self.eval_infix_big(infix, left_big, right_big)
+            }
+            (Object::BigInt(left_big), Object::Integer(right_int)) => {
+                self.eval_infix_big(infix, left_big, &BigInt::from(*right_int))
+            }
+            (Object::Integer(left_int), Object::BigInt(right_big)) => {
+                self.eval_infix_big(infix, &BigInt::from(*left_int), right_big)
+            }
+            (Object::BigInt(left_big), Object::Float(right_float)) => {
+                self.eval_infix_float(infix, left_big.to_f64(), *right_float)
+            }
+            (Object::Float(left_float), Object::BigInt(right_big)) => {
+                self.eval_infix_float(infix, *left_float, right_big.to_f64())
+            }
+            (Object::BigInt(left_big), Object::Rational(right_rational)) => {
+                self.eval_infix_rational(infix, &Rational::from(left_big.clone()), right_rational)
+            }
+            (Object::Rational(left_rational), Object::BigInt(right_big)) => {
+                self.eval_infix_rational(infix, left_rational, &Rational::from(right_big.clone()))
+            }
+            (Object::Rational(left_rational), Object::Rational(right_rational)) => {
+                self.eval_infix_rational(infix, left_rational, right_rational)
+            }
+            (Object::Rational(left_rational), Object::Integer(right_int)) => {
+                self.eval_infix_rational(infix, left_rational, &Rational::from(*right_int))
+            }
+            (Object::Integer(left_int), Object::Rational(right_rational)) => {
+                self.eval_infix_rational(infix, &Rational::from(*left_int), right_rational)
+            }
+            (Object::Rational(left_rational), Object::Float(right_float)) => {
+                self.eval_infix_float(infix, left_rational.to_f64(), *right_float)
+            }
+            (Object::Float(left_float), Object::Rational(right_rational)) => {
+                self.eval_infix_float(infix, *left_float, right_rational.to_f64())
+            }
+            (Object::String(left_string), Object::String(right_string)) => {
+                self.eval_infix_string(infix, left_string, right_string)
+            }
+            (Object::Boolean(left_bool), Object::Boolean(right_bool)) => match infix {
+                Infix::Equal => Ok(Object::Boolean(left_bool == right_bool)),
+                Infix::NotEqual => Ok(Object::Boolean(left_bool != right_bool)),
+                _ => Err(Self::infix_mismatch(infix, &left, &right)),
+            },
+            _ => Err(Self::infix_mismatch(infix, &left, &right)),
         }
-        result
     }
 
-    pub fn eval_infix(&mut self, infix: &Infix, left: Object, right: Object) -> Object {
-        match left {
-            Object::Integer(left_int) => {
-                if let Object::Integer(right_int) = right {
-                    self.eval_infix_int(infix, left_int, right_int)
-                } else {
-                    Object::Null
-                }
+    /// `x :: list` prepends `x`; `left ++ right` concatenates two lists.
+    /// Ranges are turned into lists first.
+    pub fn eval_infix_list(
+        &mut self,
+        infix: &Infix,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, RuntimeError> {
+        match (infix, left.elements(), right.elements()) {
+            (Infix::Cons, _, Some(mut list)) => {
+                list.insert(0, left);
+                Ok(Object::List(List::from(list)))
             }
-            Object::Boolean(left_bool) => {
-                if let Object::Boolean(right_bool) = right {
-                    match infix {
-                        Infix::Equal => Object::Boolean(left_bool == right_bool),
-                        Infix::NotEqual => Object::Boolean(left_bool != right_bool),
-                        _ => Object::Null,
-                    }
+            (Infix::Concat, Some(mut list), Some(tail)) => {
+                list.extend(tail);
+                Ok(Object::List(List::from(list)))
+            }
+            _ => Err(Self::infix_mismatch(infix, &left, &right)),
+        }
+    }
+
+    /// Set algebra, with `<=` and `>=` testing for subsets and supersets and
+    /// `<` and `>` for proper ones.
+    pub fn eval_infix_set(
+        &mut self,
+        infix: &Infix,
+        left: &Rc<BTreeSet<Object>>,
+        right: &Rc<BTreeSet<Object>>,
+    ) -> Result<Object, RuntimeError> {
+        let set =
+            |elements: Vec<&Object>| Object::Set(Rc::new(elements.into_iter().cloned().collect()));
+        match infix {
+            Infix::Union => Ok(set(left.union(right).collect())),
+            Infix::Intersection => Ok(set(left.intersection(right).collect())),
+            Infix::Minus => Ok(set(left.difference(right).collect())),
+            Infix::SymmetricDifference => Ok(set(left.symmetric_difference(right).collect())),
+            Infix::Equal => Ok(Object::Boolean(left == right)),
+            Infix::NotEqual => Ok(Object::Boolean(left != right)),
+            Infix::LessEqual => Ok(Object::Boolean(left.is_subset(right))),
+            Infix::GreaterEqual => Ok(Object::Boolean(left.is_superset(right))),
+            Infix::LessThan => Ok(Object::Boolean(left.is_subset(right) && left != right)),
+            Infix::GreaterThan => Ok(Object::Boolean(left.is_superset(right) && left != right)),
+            _ => Err(Self::infix_mismatch(
+                infix,
+                &Object::Set(Rc::clone(left)),
+                &Object::Set(Rc::clone(right)),
+            )),
+        }
+    }
+
+    /// `x in xs`: whether `xs`, a set, list or range, holds `x`.
+    pub fn eval_member(
+        &mut self,
+        element: Object,
+        collection: Object,
+    ) -> Result<Object, RuntimeError> {
+        let member = match (&element, &collection) {
+            _ if !element.is_hashable() => {
+                return Err(Self::infix_mismatch(&Infix::Member, &element, &collection))
+            }
+            (_, Object::Set(elements)) => elements.contains(&element),
+            (_, Object::List(elements)) => elements.contains(&element),
+            (Object::Integer(value), Object::Range(range)) => range.contains(*value),
+            (Object::String(key), Object::Map(entries)) => entries.contains_key(key),
+            _ => false,
+        };
+        Ok(Object::Boolean(member))
+    }
+
+    /// Apply a comparison operator by the total order on values.
+    fn eval_comparison(
+        infix: &Infix,
+        left: &Object,
+        right: &Object,
+    ) -> Result<Object, RuntimeError> {
+        let ordering = left.cmp(right);
+        let result = match infix {
+            Infix::LessThan => ordering.is_lt(),
+            Infix::GreaterThan => ordering.is_gt(),
+            Infix::LessEqual => ordering.is_le(),
+            Infix::GreaterEqual => ordering.is_ge(),
+            Infix::Equal => ordering.is_eq(),
+            Infix::NotEqual => ordering.is_ne(),
+            _ => return Err(Self::infix_mismatch(infix, left, right)),
+        };
+        Ok(Object::Boolean(result))
+    }
+
+    fn infix_mismatch(infix: &Infix, left: &Object, right: &Object) -> RuntimeError {
+        RuntimeError::TypeMismatch(format!(
+            "cannot apply `{}` to {} and {}",
+            infix,
+            left.type_name(),
+            right.type_name()
+        ))
+    }
+
+    pub fn eval_infix_int(
+        &mut self,
+        infix: &Infix,
+        left_int: i64,
+        right_int: i64,
+    ) -> Result<Object, RuntimeError> {
+        // Redo arithmetic that overflows 64 bits on big integers.
+        let mut checked = |value: Option<i64>| match value {
+            Some(value) => Ok(Object::Integer(value)),
+            None => self.eval_infix_big(infix, &BigInt::from(left_int), &BigInt::from(right_int)),
+        };
+        match infix {
+            Infix::Plus => checked(left_int.checked_add(right_int)),
+            Infix::Minus => checked(left_int.checked_sub(right_int)),
+            Infix::Multiply => checked(left_int.checked_mul(right_int)),
+            Infix::Divide | Infix::Modulo | Infix::ExactDivide if right_int == 0 => {
+                Err(RuntimeError::DivisionByZero)
+            }
+            Infix::Divide => checked(left_int.checked_div(right_int)),
+            Infix::Modulo => checked(left_int.checked_rem(right_int)),
+            Infix::ExactDivide => Ok(Object::rational(Rational::new(
+                BigInt::from(left_int),
+                BigInt::from(right_int),
+            ))),
+            // `2 ** -1` is the exact fraction `1/2`.
+            Infix::Exponent if right_int < 0 => self.eval_infix_rational(
+                infix,
+                &Rational::from(left_int),
+                &Rational::from(right_int),
+            ),
+            Infix::Exponent => checked(
+                u32::try_from(right_int)
+                    .ok()
+                    .and_then(|exponent| left_int.checked_pow(exponent)),
+            ),
+            Infix::LessThan => Ok(Object::Boolean(left_int < right_int)),
+            Infix::GreaterThan => Ok(Object::Boolean(left_int > right_int)),
+            Infix::LessEqual => Ok(Object::Boolean(left_int <= right_int)),
+            Infix::GreaterEqual => Ok(Object::Boolean(left_int >= right_int)),
+            Infix::Equal => Ok(Object::Boolean(left_int == right_int)),
+            Infix::NotEqual => Ok(Object::Boolean(left_int != right_int)),
+            Infix::Cons
+            | Infix::Concat
+            | Infix::Union
+            | Infix::Intersection
+            | Infix::SymmetricDifference
+            | Infix::Member
+            | Infix::Pipe => Err(Self::infix_mismatch(
+                infix,
+                &Object::Integer(left_int),
+                &Object::Integer(right_int),
+            )),
+        }
+    }
+
+    /// Arithmetic and comparison on integers that do not all fit in 64 bits,
+    /// where an Int on either side has been widened. Results that fit are
+    /// plain Ints again.
+    pub fn eval_infix_big(
+        &mut self,
+        infix: &Infix,
+        left: &BigInt,
+        right: &BigInt,
+    ) -> Result<Object, RuntimeError> {
+        match infix {
+            Infix::Plus => Ok(Object::integer(left + right)),
+            Infix::Minus => Ok(Object::integer(left - right)),
+            Infix::Multiply => Ok(Object::integer(left * right)),
+            Infix::Divide | Infix::Modulo | Infix::ExactDivide if right.is_zero() => {
+                Err(RuntimeError::DivisionByZero)
+            }
+            Infix::Divide => Ok(Object::integer(left.div_rem(right).0)),
+            Infix::Modulo => Ok(Object::integer(left.div_rem(right).1)),
+            Infix::ExactDivide => Ok(Object::rational(Rational::new(left.clone(), right.clone()))),
+            Infix::Exponent if right.is_negative() => self.eval_infix_rational(
+                infix,
+                &Rational::from(left.clone()),
+                &Rational::from(right.clone()),
+            ),
+            Infix::Exponent => match right.to_i64().map(u32::try_from) {
+                Some(Ok(exponent)) => Ok(Object::integer(left.pow(exponent))),
+                _ => Err(RuntimeError::Overflow(EXPONENT_LIMIT)),
+            },
+            Infix::LessThan => Ok(Object::Boolean(left < right)),
+            Infix::GreaterThan => Ok(Object::Boolean(left > right)),
+            Infix::LessEqual => Ok(Object::Boolean(left <= right)),
+            Infix::GreaterEqual => Ok(Object::Boolean(left >= right)),
+            Infix::Equal => Ok(Object::Boolean(left == right)),
+            Infix::NotEqual => Ok(Object::Boolean(left != right)),
+            _ => Err(Self::infix_mismatch(
+                infix,
+                &Object::integer(left.clone()),
+                &Object::integer(right.clone()),
+            )),
+        }
+    }
+
+    /// Arithmetic on floats, where a number on the other side has been
+    /// converted to Float. Follows IEEE 754, so dividing by zero gives an
+    /// infinity or NaN rather than an error. Comparisons are exact instead,
+    /// see `eval_comparison`.
+    pub fn eval_infix_float(
+        &mut self,
+        infix: &Infix,
+        left: f64,
+        right: f64,
+    ) -> Result<Object, RuntimeError> {
+        match infix {
+            Infix::Plus => Ok(Object::Float(left + right)),
+            Infix::Minus => Ok(Object::Float(left - right)),
+            Infix::Multiply => Ok(Object::Float(left * right)),
+            Infix::Divide | Infix::ExactDivide => Ok(Object::Float(left / right)),
+            Infix::Modulo => Ok(Object::Float(left % right)),
+            Infix::Exponent => Ok(Object::Float(left.powf(right))),
+            _ => Err(Self::infix_mismatch(
+                infix,
+                &Object::Float(left),
+                &Object::Float(right),
+            )),
+        }
+    }
+
+    /// Exact arithmetic and comparison on fractions, where an Int on either
+    /// side has been turned into one. Whole results come back as Ints.
+    pub fn eval_infix_rational(
+        &mut self,
+        infix: &Infix,
+        left: &Rational,
+        right: &Rational,
+    ) -> Result<Object, RuntimeError> {
+        match infix {
+            Infix::Plus => Ok(Object::rational(left + right)),
+            Infix::Minus => Ok(Object::rational(left - right)),
+            Infix::Multiply => Ok(Object::rational(left * right)),
+            Infix::Divide | Infix::ExactDivide | Infix::Modulo if right.is_zero() => {
+                Err(RuntimeError::DivisionByZero)
+            }
+            Infix::Divide | Infix::ExactDivide => Ok(Object::rational(left / right)),
+            Infix::Modulo => Ok(Object::rational(left % right)),
+            Infix::Exponent if right.is_integer() => {
+                let exponent = right.numerator();
+                let Some(power) = exponent
+                    .to_i64()
+                    .and_then(|exponent| u32::try_from(exponent.unsigned_abs()).ok())
+                else {
+                    return Err(RuntimeError::Overflow(EXPONENT_LIMIT));
+                };
+                if !exponent.is_negative() {
+                    Ok(Object::rational(left.pow(power)))
+                } else if left.is_zero() {
+                    Err(RuntimeError::DivisionByZero)
                 } else {
-                    Object::Null
+                    Ok(Object::rational(left.pow(power).recip()))
                 }
             }
-            _ => Object::Null,
+            // A fractional power is generally irrational.
+            Infix::Exponent => self.eval_infix_float(infix, left.to_f64(), right.to_f64()),
+            Infix::LessThan => Ok(Object::Boolean(left < right)),
+            Infix::GreaterThan => Ok(Object::Boolean(left > right)),
+            Infix::LessEqual => Ok(Object::Boolean(left <= right)),
+            Infix::GreaterEqual => Ok(Object::Boolean(left >= right)),
+            Infix::Equal => Ok(Object::Boolean(left == right)),
+            Infix::NotEqual => Ok(Object::Boolean(left != right)),
+            _ => Err(Self::infix_mismatch(
+                infix,
+                &Object::rational(left.clone()),
+                &Object::rational(right.clone()),
+            )),
         }
     }
 
-    pub fn eval_infix_int(&mut self, infix: &Infix, left_int: i64, right_int: i64) -> Object {
+    /// `++` joins two strings and comparisons order them by code point.
+    pub fn eval_infix_string(
+        &mut self,
+        infix: &Infix,
+        left: &str,
+        right: &str,
+    ) -> Result<Object, RuntimeError> {
         match infix {
-            Infix::Plus => Object::Integer(left_int + right_int),
-            Infix::Minus => Object::Integer(left_int - right_int),
-            Infix::Multiply => Object::Integer(left_int * right_int),
-            Infix::Divide => Object::Integer(left_int / right_int),
-            Infix::Modulo => Object::Integer(left_int % right_int),
-            Infix::Exponent => Object::Integer(left_int.pow(right_int as u32)),
-            Infix::LessThan => Object::Boolean(left_int < right_int),
-            Infix::GreaterThan => Object::Boolean(left_int > right_int),
-            Infix::Equal => Object::Boolean(left_int == right_int),
-            Infix::NotEqual => Object::Boolean(left_int != right_int),
-            Infix::Cons => Object::Null,
+            Infix::Concat => Ok(Object::String(format!("{}{}", left, right))),
+            Infix::LessThan => Ok(Object::Boolean(left < right)),
+            Infix::GreaterThan => Ok(Object::Boolean(left > right)),
+            Infix::LessEqual => Ok(Object::Boolean(left <= right)),
+            Infix::GreaterEqual => Ok(Object::Boolean(left >= right)),
+            Infix::Equal => Ok(Object::Boolean(left == right)),
+            Infix::NotEqual => Ok(Object::Boolean(left != right)),
+            _ => Err(Self::infix_mismatch(
+                infix,
+                &Object::String(left.to_string()),
+                &Object::String(right.to_string()),
+            )),
         }
     }
 
-    pub fn eval_prefix(&mut self, prefix: &Prefix, object: Object) -> Object {
+    pub fn eval_prefix(&mut self, prefix: &Prefix, object: Object) -> Result<Object, RuntimeError> {
         match prefix {
-            Prefix::Not => self.eval_not_prefix(object),
+            Prefix::Not => Ok(self.eval_not_prefix(object)),
             Prefix::Plus => self.eval_plus_prefix(object),
             Prefix::Minus => self.eval_minus_prefix(object),
             Prefix::Cardinal => self.eval_cardinal_prefix(object),
         }
     }
 
+    pub fn eval_postfix(
+        &mut self,
+        postfix: &Postfix,
+        object: Object,
+    ) -> Result<Object, RuntimeError> {
+        match postfix {
+            Postfix::Cast(cast) => self.eval_cast(cast, object),
+            Postfix::Field(name) => Self::eval_field(name, object),
+            Postfix::Index(index) => Self::eval_index(*index as i128, object),
+            Postfix::Try => Self::eval_try(object),
+        }
+    }
+
+    /// `x?`: the value inside `Ok`, or an early return of `Error` or `None`.
+    pub fn eval_try(object: Object) -> Result<Object, RuntimeError> {
+        match object {
+            Object::Ok(value) => Ok(*value),
+            failure @ (Object::Error(_) | Object::None) => {
+                Err(RuntimeError::EarlyReturn(Box::new(failure)))
+            }
+            other => Err(RuntimeError::TypeMismatch(format!(
+                "cannot apply `?` to {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    /// `Cell.Dead`: look up a member of a value by name.
+    pub fn eval_field(name: &str, object: Object) -> Result<Object, RuntimeError> {
+        match &object {
+            Object::Type {
+                name: type_name,
+                variants,
+            } => match variants.iter().find(|variant| variant.tag == name) {
+                Some(variant) => Ok(Self::variant(type_name, variant)),
+                None => Err(RuntimeError::TypeMismatch(format!(
+                    "`{}` has no variant `{}`",
+                    type_name, name
+                ))),
+            },
+            Object::Map(entries) => entries.get(name).cloned().ok_or_else(|| {
+                RuntimeError::InvalidValue(format!("no field `{}` in {}", name, object))
+            }),
+            other => Err(RuntimeError::TypeMismatch(format!(
+                "{} has no field `{}`",
+                other.type_name(),
+                name
+            ))),
+        }
+    }
+
+    /// `xs.0` or `xs.[i]`: the element of a list or range, or the
+    /// character of a string, at `index`, counting from zero. `index` holds
+    /// any Int as well as any position written as in `.1`.
+    pub fn eval_index(index: i128, object: Object) -> Result<Object, RuntimeError> {
+        let position = usize::try_from(index).ok();
+        let (element, length) = match &object {
+            Object::List(elements) => (
+                position.and_then(|position| elements.get(position).cloned()),
+                elements.len() as u128,
+            ),
+            Object::Range(range) => (
+                u128::try_from(index)
+                    .ok()
+                    .and_then(|position| range.get(position))
+                    .map(Object::Integer),
+                range.len(),
+            ),
+            Object::String(value) => (
+                position
+                    .and_then(|position| value.chars().nth(position))
+                    .map(|character| Object::String(character.to_string())),
+                value.chars().count() as u128,
+            ),
+            other => {
+                return Err(RuntimeError::TypeMismatch(format!(
+                    "cannot index into {}",
+                    other.type_name()
+                )))
+            }
+        };
+        element.ok_or_else(|| Self::out_of_bounds(index, length))
+    }
+
+    /// `xs.[1..3]`: the elements of a list or range, or the characters of a
+    /// string, at each position of `range` in turn.
+    pub fn eval_slice(range: Range, object: Object) -> Result<Object, RuntimeError> {
+        let mismatch = |other: &Object| {
+            RuntimeError::TypeMismatch(format!("cannot slice {}", other.type_name()))
+        };
+        let length = match &object {
+            Object::List(elements) => elements.len() as u128,
+            Object::Range(range) => range.len(),
+            Object::String(value) => value.chars().count() as u128,
+            other => return Err(mismatch(other)),
+        };
+        // The ends of a progression bound every position in between.
+        let ends = [range.get(0), range.get(range.len().saturating_sub(1))];
+        if let Some(position) = ends
+            .into_iter()
+            .flatten()
+            .find(|position| u128::try_from(*position).map_or(true, |position| position >= length))
+        {
+            return Err(Self::out_of_bounds(position.into(), length));
+        }
+        let positions = range.iter().map(|position| position as usize);
+        match object {
+            Object::String(value) => {
+                let characters: Vec<char> = value.chars().collect();
+                Ok(Object::String(
+                    positions.map(|position| characters[position]).collect(),
+                ))
+            }
+            // Only the selected elements of a range are computed.
+            Object::Range(elements) => Ok(Object::List(
+                positions
+                    .filter_map(|position| elements.get(position as u128))
+                    .map(Object::Integer)
+                    .collect(),
+            )),
+            Object::List(elements) => Ok(Object::List(
+                positions
+                    .map(|position| elements[position].clone())
+                    .collect(),
+            )),
+            other => Err(mismatch(&other)),
+        }
+    }
+
+    fn out_of_bounds(index: i128, length: u128) -> RuntimeError {
+        RuntimeError::InvalidValue(format!(
+            "index {} is out of bounds for a length of {}",
+            index, length
+        ))
+    }
+
+    /// `{record with key: value}`: a copy of the map `record` with each key
+    /// set to its new value, added if it was not there.
+    pub fn eval_update(
+        &mut self,
+        record: &Expression,
+        fields: &[(String, Expression)],
+    ) -> Result<Object, RuntimeError> {
+        let mut entries = match self.eval_expression(record)? {
+            Object::Map(entries) => entries,
+            other => {
+                self.span = record.span;
+                return Err(RuntimeError::TypeMismatch(format!(
+                    "cannot update the fields of {}",
+                    other.type_name()
+                )));
+            }
+        };
+        for (key, value) in fields {
+            let value = self.eval_expression(value)?;
+            Rc::make_mut(&mut entries).insert(key.clone(), value);
+        }
+        Ok(Object::Map(entries))
+    }
+
+    pub fn eval_cast(&mut self, cast: &Cast, object: Object) -> Result<Object, RuntimeError> {
+        match (cast, object) {
+            (Cast::Set, object @ (Object::List(_) | Object::Range(_) | Object::Set(_))) => {
+                Object::set(Self::iterate(object)?)
+            }
+            (Cast::String, object) => Ok(Object::String(object.to_string())),
+            (Cast::Int, object @ (Object::Integer(_) | Object::BigInt(_))) => Ok(object),
+            (Cast::Int, Object::Boolean(value)) => Ok(Object::Integer(i64::from(value))),
+            // Floats and Rationals drop their fractional part, rounding
+            // towards zero.
+            (Cast::Int, Object::Float(value)) => {
+                BigInt::from_f64(value).map(Object::integer).ok_or_else(|| {
+                    RuntimeError::InvalidValue(format!("{:?} does not fit in an Int", value))
+                })
+            }
+            (Cast::Int, Object::Rational(value)) => Ok(Object::integer(value.trunc())),
+            (Cast::Float, Object::Rational(value)) => Ok(Object::Float(value.to_f64())),
+            (Cast::Float, object @ Object::Float(_)) => Ok(object),
+            (Cast::Float, Object::Integer(value)) => Ok(Object::Float(value as f64)),
+            (Cast::Float, Object::BigInt(value)) => Ok(Object::Float(value.to_f64())),
+            (Cast::Float, Object::String(value)) => match value.parse() {
+                Ok(value) => Ok(Object::Float(value)),
+                Err(_) => Err(RuntimeError::InvalidValue(format!(
+                    "{:?} is not a Float",
+                    value
+                ))),
+            },
+            (Cast::Int, Object::String(value)) => match BigInt::parse(&value) {
+                Some(value) => Ok(Object::integer(value)),
+                None => Err(RuntimeError::InvalidValue(format!(
+                    "{:?} is not an Int",
+                    value
+                ))),
+            },
+            (cast, other) => Err(RuntimeError::TypeMismatch(format!(
+                "cannot cast {} to {}",
+                other.type_name(),
+                match cast {
+                    Cast::Set => "Set",
+                    Cast::Int => "Int",
+                    Cast::Float => "Float",
+                    Cast::String => "String",
+                }
+            ))),
+        }
+    }
+
     pub fn eval_not_prefix(&mut self, object: Object) -> Object {
         match object {
             Object::Boolean(true) => Object::Boolean(false),
@@ -153,33 +1434,263 @@ impl Evaluator {
         }
     }
 
-    pub fn eval_plus_prefix(&mut self, object: Object) -> Object {
+    pub fn eval_plus_prefix(&mut self, object: Object) -> Result<Object, RuntimeError> {
         match object {
-            Object::Integer(value) => Object::Integer(value),
-            _ => Object::Null,
+            object @ (Object::Integer(_)
+            | Object::BigInt(_)
+            | Object::Float(_)
+            | Object::Rational(_)) => Ok(object),
+            other => Err(RuntimeError::TypeMismatch(format!(
+                "cannot apply unary `+` to {}",
+                other.type_name()
+            ))),
         }
     }
 
-    pub fn eval_minus_prefix(&mut self, object: Object) -> Object {
+    pub fn eval_minus_prefix(&mut self, object: Object) -> Result<Object, RuntimeError> {
         match object {
-            Object::Integer(value) => Object::Integer(-1 * value),
-            _ => Object::Null,
+            Object::Integer(value) => Ok(match value.checked_neg() {
+                Some(value) => Object::Integer(value),
+                None => Object::BigInt(-&BigInt::from(value)),
+            }),
+            Object::BigInt(value) => Ok(Object::integer(-&value)),
+            Object::Float(value) => Ok(Object::Float(-value)),
+            Object::Rational(value) => Ok(Object::Rational(-&value)),
+            other => Err(RuntimeError::TypeMismatch(format!(
+                "cannot apply unary `-` to {}",
+                other.type_name()
+            ))),
         }
     }
 
-    pub fn eval_cardinal_prefix(&mut self, object: Object) -> Object {
+    pub fn eval_cardinal_prefix(&mut self, object: Object) -> Result<Object, RuntimeError> {
         match object {
-            Object::List(value) => Object::Integer(value.len() as i64),
-            _ => Object::Integer(1),
+            Object::List(value) => Ok(Object::Integer(value.len() as i64)),
+            Object::Set(value) => Ok(Object::Integer(value.len() as i64)),
+            Object::Map(value) => Ok(Object::Integer(value.len() as i64)),
+            Object::String(value) => Ok(Object::Integer(value.chars().count() as i64)),
+            Object::Range(range) => Ok(Object::integer(BigInt::from(range.len()))),
+            _ => Ok(Object::Integer(1)),
         }
     }
 
-    pub fn eval_literal(&mut self, literal: &Literal) -> Object {
+    pub fn eval_literal(&mut self, literal: &Literal) -> Result<Object, RuntimeError> {
         match literal {
-            Literal::Integer(value) => Object::Integer(*value),
-            Literal::Boolean(value) => Object::Boolean(*value),
-            Literal::String(value) => Object::String(value.clone()),
-            _ => unreachable!("List/Hash doesn't work."),
+            Literal::Integer(value) => Ok(Object::integer(value.clone())),
+            Literal::Float(value) => Ok(Object::Float(*value)),
+            Literal::Boolean(value) => Ok(Object::Boolean(*value)),
+            Literal::String(value) => Ok(Object::String(value.clone())),
+            Literal::List(elements) => {
+                let mut list = vec![];
+                for element in elements {
+                    list.push(self.eval_expression(element)?);
+                }
+                Ok(Object::List(List::from(list)))
+            }
+            Literal::Map(fields) => {
+                let mut entries = BTreeMap::new();
+                for (key, expression) in fields {
+                    let value = self.eval_expression(expression)?;
+                    if entries.insert(key.clone(), value).is_some() {
+                        self.span = expression.span;
+                        return Err(RuntimeError::InvalidValue(format!(
+                            "the key `{}` appears more than once",
+                            key
+                        )));
+                    }
+                }
+                Ok(Object::Map(Rc::new(entries)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn run(source: &str) -> Result<Object, RuntimeError> {
+        run_with(Evaluator::new(), source)
+    }
+
+    fn run_with(mut evaluator: Evaluator, source: &str) -> Result<Object, RuntimeError> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse();
+        let diagnostics = parser.diagnostics();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        evaluator.eval(&program).map_err(|error| error.node)
+    }
+
+    /// The value of `source` as it prints.
+    fn eval(source: &str) -> String {
+        match run(source) {
+            Ok(value) => value.to_string(),
+            Err(error) => panic!("{} failed: {}", source, error),
+        }
+    }
+
+    #[test]
+    fn return_unwinds_through_a_let() {
+        let source = "let f = fn x -> {
+            let y = if x > 0 { return 1 } else { 0 };
+            y + 10
+        };";
+        assert_eq!(eval(&format!("{} f 5;", source)), "1");
+        assert_eq!(eval(&format!("{} f 0;", source)), "10");
+    }
+
+    #[test]
+    fn return_unwinds_through_a_list() {
+        let source = "let f = fn x -> [if x > 0 { return 1 } else { 0 }, 5];";
+        assert_eq!(eval(&format!("{} f 5;", source)), "1");
+        assert_eq!(eval(&format!("{} f 0;", source)), "[0, 5]");
+    }
+
+    #[test]
+    fn return_at_the_top_level_ends_the_program() {
+        assert_eq!(eval("let y = if true { return 1 } else { 0 }; y + 5;"), "1");
+        assert_eq!(
+            eval("let y = if false { return 1 } else { 0 }; y + 5;"),
+            "5"
+        );
+        assert_eq!(eval("return 2; 3;"), "2");
+    }
+
+    #[test]
+    fn values_holding_functions_have_no_equality() {
+        let functions = "let f = fn x -> x; let g = fn y -> 1;";
+        for comparison in [
+            "f == f",
+            "f != g",
+            "[fn x -> x] == [fn y -> 1]",
+            "[f] != [f]",
+            "Ok f == Ok g",
+            "{a: f} == {a: g}",
+            "[[1], [f]] == [[1], [f]]",
+            "[f] in [[f]]",
+        ] {
+            let source = format!("{} {};", functions, comparison);
+            assert!(
+                matches!(run(&source), Err(RuntimeError::TypeMismatch(_))),
+                "{}",
+                comparison
+            );
         }
+        assert_eq!(eval("[1, [2]] == [1, [2]];"), "true");
+        assert_eq!(eval("let f = fn x -> x; [1] == [f 1];"), "true");
+    }
+
+    #[test]
+    fn positions_past_i64_are_reported_as_written() {
+        let error = run("[1, 2].18446744073709551615;").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid value: index 18446744073709551615 is out of bounds for a length of 2"
+        );
+        let every = "[-9223372036854775808..9223372036854775807]";
+        assert_eq!(
+            eval(&format!("{}.18446744073709551615;", every)),
+            "9223372036854775807"
+        );
+        assert_eq!(
+            eval(&format!("{}.[-1 + 1];", every)),
+            "-9223372036854775808"
+        );
+    }
+
+    #[test]
+    fn fewer_arguments_return_a_function_awaiting_the_rest() {
+        let sum = "let sum = fn x, y, z -> x + y + z;";
+        assert_eq!(eval(&format!("{} let inc = sum 1 2; inc 39;", sum)), "42");
+        assert_eq!(eval(&format!("{} let f = sum 1; f 2 39;", sum)), "42");
+        assert_eq!(eval(&format!("{} let f = sum 1; (f 2) 39;", sum)), "42");
+        assert_eq!(eval(&format!("{} 39 |> sum 1 2;", sum)), "42");
+        // Each partial application keeps its own arguments.
+        assert_eq!(
+            eval(&format!("{} let f = sum 1; [f 2 3, f 10 20];", sum)),
+            "[6, 31]"
+        );
+    }
+
+    #[test]
+    fn extra_arguments_apply_the_result_to_the_rest() {
+        let make_adder = "let make_adder = fn x -> fn y -> x + y;";
+        assert_eq!(eval(&format!("{} make_adder 1 2;", make_adder)), "3");
+        assert_eq!(
+            eval("let pick = fn x -> fn y, z -> x * y + z; pick 2 3 4;"),
+            "10"
+        );
+        assert!(matches!(
+            run("let id = fn x -> x; id 1 2;"),
+            Err(RuntimeError::ArityMismatch {
+                expected: 1,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn question_mark_unwraps_ok_inside_nested_expressions() {
+        let divide =
+            "let divide = fn x, y -> if y == 0 { Error \"div by zero\" } else { Ok (x / y) };";
+        assert_eq!(eval(&format!("{} [divide 10 2?, 1];", divide)), "[5, 1]");
+        assert_eq!(eval(&format!("{} 1 + (divide 10 2?) * 2;", divide)), "11");
+        assert_eq!(
+            eval(&format!("{} (divide (divide 20 2?) 5?);", divide)),
+            "2"
+        );
+        assert_eq!(eval("{a: Ok [Ok 1]?};"), "{a: [Ok 1]}");
+    }
+
+    #[test]
+    fn question_mark_returns_error_and_none_from_the_enclosing_function() {
+        let functions = "
+            let divide = fn x, y -> if y == 0 { Error \"div by zero\" } else { Ok (x / y) };
+            let first = fn xs -> match xs | [] -> None | x :: rest -> Ok x;
+            let quarter = fn x, y -> Ok [1 + (divide (divide x y?) 2?), 0];
+            let head = fn xs -> { let x = [first xs?]; Ok x };";
+        assert_eq!(eval(&format!("{} quarter 20 2;", functions)), "Ok [6, 0]");
+        assert_eq!(
+            eval(&format!("{} quarter 20 0;", functions)),
+            "Error \"div by zero\""
+        );
+        assert_eq!(eval(&format!("{} head [7];", functions)), "Ok [7]");
+        assert_eq!(eval(&format!("{} head [];", functions)), "None");
+        // Only the innermost function returns.
+        assert_eq!(
+            eval(&format!("{} [quarter 1 0, Ok 2];", functions)),
+            "[Error \"div by zero\", Ok 2]"
+        );
+        assert!(matches!(
+            run(&format!("{} [1, divide 1 0?];", functions)),
+            Err(RuntimeError::EarlyReturn(_))
+        ));
+    }
+
+    #[test]
+    fn tail_recursion_runs_in_constant_stack() {
+        let count = "let count = fn n, acc -> if n == 0 { acc } else { count (n - 1) (acc + 1) };";
+        assert_eq!(eval(&format!("{} count 1000000 0;", count)), "1000000");
+        // Tail calls do not count towards the depth either.
+        let limited = Evaluator::new().max_depth(10);
+        let source = format!("{} count 1000 0;", count);
+        assert_eq!(run_with(limited, &source).unwrap().to_string(), "1000");
+    }
+
+    #[test]
+    fn nested_calls_stop_at_the_maximum_depth() {
+        let depth = "let depth = fn n -> if n == 0 { 0 } else { 1 + depth (n - 1) };";
+        let limited = || Evaluator::new().max_depth(50);
+        let within = format!("{} depth 49;", depth);
+        assert_eq!(run_with(limited(), &within).unwrap().to_string(), "49");
+        let beyond = format!("{} depth 50;", depth);
+        assert!(matches!(
+            run_with(limited(), &beyond),
+            Err(RuntimeError::RecursionLimit(50))
+        ));
+        let error = run_with(limited(), &beyond).unwrap_err();
+        assert_eq!(error.to_string(), "maximum recursion depth of 50 exceeded");
     }
 }
@@ -1,16 +1,392 @@
-use super::*;
+use super::environment::Environment;
+use super::error::RuntimeError;
+use super::list::List;
+use super::range::Range;
+use super::rational::Rational;
+use crate::ast::{Identifier, Program, Variant};
+use crate::bigint::BigInt;
+use crate::lexer::Token;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
-#[derive(Debug)]
+use std::rc::Rc;
+#[derive(Debug, Clone)]
 pub enum Object {
     Null,
     Integer(i64),
+    /// An Int too large for 64 bits. Ints that fit are always `Integer`.
+    BigInt(BigInt),
+    Float(f64),
+    /// An exact fraction, such as `1/3`. Whole values are always `Integer`.
+    Rational(Rational),
     Boolean(bool),
     String(String),
-    Return(Box<Object>),
-    // TODO: List
-    List(Vec<Object>),
-    // TODO: Type (represented as an enum?)
-    // Type(Vec<Identifier>),
+    Function {
+        parameters: Vec<Identifier>,
+        body: Rc<Program>,
+        env: Rc<RefCell<Environment>>,
+    },
+    List(List),
+    Range(Range),
+    /// Shared like `List`, so that reading a set never copies it.
+    Set(Rc<BTreeSet<Object>>),
+    /// Values keyed by field name or string, kept sorted by key so that
+    /// iteration and printing do not depend on the order they were added in.
+    Map(Rc<BTreeMap<String, Object>>),
+    /// A tagged union declared with `let Cell = type | Alive of Int | ...`.
+    Type {
+        name: String,
+        variants: Vec<Variant>,
+    },
+    /// A value of a tagged union, such as `Cell.Dead 0` or `Cell.Dormant`.
+    Variant {
+        type_name: String,
+        tag: String,
+        payload: Option<Box<Object>>,
+    },
+    /// The success of a computation, `Ok 5`.
+    Ok(Box<Object>),
+    /// A failure, carrying a message or other value, or `Null` when written
+    /// as a bare `Error`.
+    Error(Box<Object>),
+    /// The absence of a value.
+    None,
+    /// The function building a variant that carries a payload of type
+    /// `payload`, such as `Cell.Dead`.
+    Constructor {
+        type_name: String,
+        tag: String,
+        payload: String,
+    },
+}
+
+impl Object {
+    /// The name of this value's type as it is shown in runtime errors.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Object::Null => "Null",
+            Object::Integer(_) | Object::BigInt(_) => "Int",
+            Object::Float(_) => "Float",
+            Object::Rational(_) => "Rational",
+            Object::Boolean(_) => "Bool",
+            Object::String(_) => "String",
+            Object::Function { .. } | Object::Constructor { .. } => "Function",
+            Object::List(_) => "List",
+            Object::Range(_) => "Range",
+            Object::Set(_) => "Set",
+            Object::Map(_) => "Map",
+            Object::Type { .. } => "Type",
+            Object::Variant { type_name, .. } => type_name,
+            Object::Ok(_) | Object::Error(_) | Object::None => "Result",
+        }
+    }
+
+    /// This value seen as a variant of a tagged union: its type, tag and
+    /// payload. `Ok`, `Error` and `None` are the variants of `Result`.
+    pub fn as_variant(&self) -> Option<(&str, &str, Option<&Object>)> {
+        match self {
+            Object::Variant {
+                type_name,
+                tag,
+                payload,
+            } => Some((type_name, tag, payload.as_deref())),
+            Object::Ok(value) => Some(("Result", "Ok", Some(value))),
+            Object::Error(value) => Some(("Result", "Error", Some(value))),
+            Object::None => Some(("Result", "None", None)),
+            _ => None,
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(
+            self,
+            Object::Integer(_) | Object::BigInt(_) | Object::Float(_) | Object::Rational(_)
+        )
+    }
+
+    /// Whether this value may be stored where the type named `name` is
+    /// expected, as in the payload of `| Alive of Int`.
+    pub fn has_type(&self, name: &str) -> bool {
+        match self {
+            Object::Range(_) => name == "Range" || name == "List",
+            other => other.type_name() == name,
+        }
+    }
+
+    /// Where values of each type sort relative to values of other types.
+    fn rank(&self) -> u8 {
+        match self {
+            Object::Null => 0,
+            Object::Boolean(_) => 1,
+            Object::Integer(_) | Object::BigInt(_) | Object::Float(_) | Object::Rational(_) => 2,
+            Object::String(_) => 3,
+            Object::List(_) | Object::Range(_) => 4,
+            Object::Set(_) => 5,
+            Object::Map(_) => 6,
+            Object::Variant { .. } | Object::Ok(_) | Object::Error(_) | Object::None => 7,
+            Object::Type { .. } => 8,
+            Object::Constructor { .. } => 9,
+            Object::Function { .. } => 10,
+        }
+    }
+
+    /// `value` as a 64-bit Int when it fits, or a big one otherwise.
+    pub fn integer(value: BigInt) -> Object {
+        match value.to_i64() {
+            Some(value) => Object::Integer(value),
+            None => Object::BigInt(value),
+        }
+    }
+
+    /// `value` as an Int when it is whole, or a Rational otherwise.
+    pub fn rational(value: Rational) -> Object {
+        if value.is_integer() {
+            Object::integer(value.numerator().clone())
+        } else {
+            Object::Rational(value)
+        }
+    }
+
+    /// Collect `elements` into a set, dropping duplicates. Fails if any of
+    /// them is or contains a function, which has no equality to dedupe by.
+    pub fn set(elements: impl IntoIterator<Item = Object>) -> Result<Object, RuntimeError> {
+        let mut set = BTreeSet::new();
+        for element in elements {
+            if !element.is_hashable() {
+                return Err(RuntimeError::TypeMismatch(
+                    "a Function cannot be an element of a Set".to_string(),
+                ));
+            }
+            set.insert(element);
+        }
+        Ok(Object::Set(Rc::new(set)))
+    }
+
+    /// Whether this value has an equality, which a function has not, and
+    /// so neither does a value holding one.
+    pub fn is_hashable(&self) -> bool {
+        match self {
+            Object::Function { .. } => false,
+            Object::List(elements) => elements.iter().all(Object::is_hashable),
+            Object::Map(entries) => entries.values().all(Object::is_hashable),
+            Object::Variant { payload, .. } => payload.iter().all(|value| value.is_hashable()),
+            Object::Ok(value) | Object::Error(value) => value.is_hashable(),
+            _ => true,
+        }
+    }
+
+    /// The elements of a list, or of a range as a list.
+    pub fn elements(&self) -> Option<Vec<Object>> {
+        match self {
+            Object::List(elements) => Some(elements.to_vec()),
+            Object::Range(range) => Some(range.iter().map(Object::Integer).collect()),
+            _ => None,
+        }
+    }
+
+    /// Write this value as it appears inside a collection, where strings are
+    /// quoted so that `["a, b"]` and `["a", "b"]` stay distinguishable.
+    fn fmt_nested(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::String(value) => write!(f, "{:?}", value),
+            other => write!(f, "{}", other),
+        }
+    }
+
+    /// Write a variant as it is built, as in `Alive 3` or `Wrap (Alive 3)`.
+    fn fmt_variant(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_variant() {
+            Some((_, tag, Some(payload))) if payload.is_compound() => {
+                write!(f, "{} ({})", tag, payload)
+            }
+            Some((_, tag, Some(payload))) => {
+                write!(f, "{} ", tag)?;
+                payload.fmt_nested(f)
+            }
+            Some((_, tag, None)) => write!(f, "{}", tag),
+            None => write!(f, "{}", self),
+        }
+    }
+
+    /// Whether this value needs parentheses to be the payload of a variant.
+    fn is_compound(&self) -> bool {
+        match self {
+            Object::Error(message) => !matches!(**message, Object::Null),
+            other => matches!(other.as_variant(), Some((_, _, Some(_)))),
+        }
+    }
+
+    /// Write a map key bare when it reads as a field name, quoted otherwise.
+    fn fmt_key(f: &mut fmt::Formatter, key: &str) -> fmt::Result {
+        let mut chars = key.chars();
+        let is_name = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_');
+        if is_name {
+            write!(f, "{}", key)
+        } else {
+            write!(f, "{:?}", key)
+        }
+    }
+
+    fn fmt_elements<'a>(
+        f: &mut fmt::Formatter,
+        elements: impl IntoIterator<Item = &'a Object>,
+    ) -> fmt::Result {
+        for (index, element) in elements.into_iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            element.fmt_nested(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Structural equality, consistent with the ordering below. A value that is
+/// or holds a function is never equal to anything, not even to itself.
+impl PartialEq for Object {
+    fn eq(&self, other: &Object) -> bool {
+        self.is_hashable() && other.is_hashable() && self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Object {}
+
+impl PartialOrd for Object {
+    fn partial_cmp(&self, other: &Object) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Order floats by value, with `-0.0` equal to `0.0` and NaN after every
+/// number, so that the order stays total.
+fn compare_floats(left: f64, right: f64) -> Ordering {
+    left.partial_cmp(&right)
+        .unwrap_or_else(|| left.is_nan().cmp(&right.is_nan()))
+}
+
+/// Order an integer against a float by their exact values, which converting
+/// either one to the other's type could round.
+fn compare_int_float(left: i64, right: f64) -> Ordering {
+    // 2^63, the first float above every i64.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if right.is_nan() || right >= LIMIT {
+        return Ordering::Less;
+    }
+    if right < -LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = right.trunc();
+    left.cmp(&(whole as i64))
+        .then_with(|| compare_floats(whole, right))
+}
+
+/// Order a big integer against a float by their exact values.
+fn compare_big_float(left: &BigInt, right: f64) -> Ordering {
+    match BigInt::from_f64(right) {
+        Some(whole) => left
+            .cmp(&whole)
+            .then_with(|| compare_floats(right.trunc(), right)),
+        None if right.is_nan() || right > 0.0 => Ordering::Less,
+        None => Ordering::Greater,
+    }
+}
+
+/// Order a fraction against a float by their exact values. A finite float
+/// is `mantissa * 2^exponent`, so `n/d` against it is `n` against
+/// `mantissa * d * 2^exponent`, scaled to keep both sides whole.
+fn compare_rational_float(left: &Rational, right: f64) -> Ordering {
+    if !right.is_finite() {
+        return if right == f64::NEG_INFINITY {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        };
+    }
+    let bits = right.to_bits();
+    let field = ((bits >> 52) & 0x7ff) as usize;
+    let fraction = (bits & ((1 << 52) - 1)) as i64;
+    // Subnormal floats have no implicit leading one.
+    let (mantissa, exponent) = if field == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), field as i64 - 1075)
+    };
+    let mantissa = if right < 0.0 { -mantissa } else { mantissa };
+    let numerator = left.numerator();
+    let scaled = &BigInt::from(mantissa) * left.denominator();
+    if exponent < 0 {
+        numerator.shift_left(-exponent as usize).cmp(&scaled)
+    } else {
+        numerator.cmp(&scaled.shift_left(exponent as usize))
+    }
+}
+
+/// A total order over values so they can be kept in a `Set`. Values of
+/// different types are ordered by type, except that integers and floats
+/// compare by value; lists and ranges compare element by element, so a
+/// range sorts next to the list holding the same elements.
+/// Functions cannot be set elements, so their relative order is meaningless.
+impl Ord for Object {
+    fn cmp(&self, other: &Object) -> Ordering {
+        if let (Some(left), Some(right)) = (self.as_variant(), other.as_variant()) {
+            return left.cmp(&right);
+        }
+        match (self, other) {
+            (Object::Integer(left), Object::Integer(right)) => left.cmp(right),
+            (Object::Float(left), Object::Float(right)) => compare_floats(*left, *right),
+            (Object::Integer(left), Object::Float(right)) => compare_int_float(*left, *right),
+            (Object::Float(left), Object::Integer(right)) => {
+                compare_int_float(*right, *left).reverse()
+            }
+            (Object::BigInt(left), Object::BigInt(right)) => left.cmp(right),
+            (Object::BigInt(left), Object::Integer(right)) => left.cmp(&BigInt::from(*right)),
+            (Object::Integer(left), Object::BigInt(right)) => BigInt::from(*left).cmp(right),
+            (Object::BigInt(left), Object::Float(right)) => compare_big_float(left, *right),
+            (Object::Float(left), Object::BigInt(right)) => {
+                compare_big_float(right, *left).reverse()
+            }
+            // `b` against `n/d` is `b * d` against `n`, as `d` is positive.
+            (Object::BigInt(left), Object::Rational(right)) => {
+                (left * right.denominator()).cmp(right.numerator())
+            }
+            (Object::Rational(_), Object::BigInt(_)) => other.cmp(self).reverse(),
+            (Object::Rational(left), Object::Rational(right)) => left.cmp(right),
+            (Object::Rational(left), Object::Integer(right)) => left.cmp(&Rational::from(*right)),
+            (Object::Integer(left), Object::Rational(right)) => Rational::from(*left).cmp(right),
+            (Object::Rational(left), Object::Float(right)) => compare_rational_float(left, *right),
+            (Object::Float(left), Object::Rational(right)) => {
+                compare_rational_float(right, *left).reverse()
+            }
+            (Object::Boolean(left), Object::Boolean(right)) => left.cmp(right),
+            (Object::String(left), Object::String(right)) => left.cmp(right),
+            (Object::Set(left), Object::Set(right)) => left.cmp(right),
+            (Object::Map(left), Object::Map(right)) => left.cmp(right),
+            (Object::List(left), Object::List(right)) => left[..].cmp(&right[..]),
+            // Two progressions agree everywhere once their first two
+            // elements do, after which the shorter one sorts first.
+            (Object::Range(left), Object::Range(right)) => left
+                .iter()
+                .take(2)
+                .cmp(right.iter().take(2))
+                .then(left.len().cmp(&right.len())),
+            (Object::Range(range), Object::List(list)) => {
+                range.iter().map(Object::Integer).cmp(list.iter().cloned())
+            }
+            (Object::List(_), Object::Range(_)) => other.cmp(self).reverse(),
+            (
+                Object::Constructor { type_name, tag, .. },
+                Object::Constructor {
+                    type_name: other_type,
+                    tag: other_tag,
+                    ..
+                },
+            ) => (type_name, tag).cmp(&(other_type, other_tag)),
+            (Object::Type { name, .. }, Object::Type { name: other, .. }) => name.cmp(other),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
 }
 
 impl fmt::Display for Object {
@@ -18,10 +394,191 @@ impl fmt::Display for Object {
         match *self {
             Object::Null => write!(f, "null"),
             Object::Integer(ref value) => write!(f, "{}", value),
+            Object::BigInt(ref value) => write!(f, "{}", value),
+            // Debug keeps the fractional part of whole floats, as in `2.0`.
+            Object::Float(ref value) => write!(f, "{:?}", value),
+            Object::Rational(ref value) => write!(f, "{}", value),
             Object::Boolean(ref value) => write!(f, "{}", value),
             Object::String(ref value) => write!(f, "{}", value),
-            Object::Return(ref value) => write!(f, "{}", value),
-            Object::List(ref value) => write!(f, "{:?}", value),
+            Object::List(ref elements) => {
+                write!(f, "[")?;
+                Object::fmt_elements(f, elements.iter())?;
+                write!(f, "]")
+            }
+            Object::Set(ref elements) => {
+                write!(f, "{{")?;
+                Object::fmt_elements(f, elements.iter())?;
+                write!(f, "}}")
+            }
+            Object::Map(ref entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    Object::fmt_key(f, key)?;
+                    write!(f, ": ")?;
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+            Object::Range(ref range) => write!(f, "{}", range),
+            Object::Type { ref variants, .. } => {
+                write!(f, "type")?;
+                for variant in variants {
+                    write!(f, " | {}", variant.tag)?;
+                    if let Some(payload) = &variant.payload {
+                        write!(f, " of {}", payload)?;
+                    }
+                }
+                Ok(())
+            }
+            Object::Variant { .. } | Object::Ok(_) | Object::None => self.fmt_variant(f),
+            Object::Error(ref message) if matches!(**message, Object::Null) => write!(f, "Error"),
+            Object::Error(_) => self.fmt_variant(f),
+            Object::Constructor {
+                ref type_name,
+                ref tag,
+                ..
+            } => write!(f, "{}.{}", type_name, tag),
+            Object::Function { ref parameters, .. } => {
+                let names: Vec<String> = parameters
+                    .iter()
+                    .filter_map(|parameter| match parameter {
+                        Token::Identifier(name) => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                write!(f, "fn({})", names.join(", "))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2^63, one past `i64::MAX`.
+    const TWO_63: f64 = 9_223_372_036_854_775_808.0;
+
+    #[test]
+    fn ints_and_floats_compare_exactly_past_2_53() {
+        let above = (1i64 << 53) + 1;
+        // `above as f64` rounds down to 2^53.
+        assert_eq!(
+            compare_int_float(above, (1i64 << 53) as f64),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_int_float(above - 1, (1i64 << 53) as f64),
+            Ordering::Equal
+        );
+        assert_eq!(compare_int_float(above, (above + 1) as f64), Ordering::Less);
+    }
+
+    #[test]
+    fn ints_and_floats_compare_exactly_at_2_63() {
+        assert_eq!(compare_int_float(i64::MAX, TWO_63), Ordering::Less);
+        assert_eq!(compare_int_float(i64::MAX, i64::MAX as f64), Ordering::Less);
+        assert_eq!(compare_int_float(i64::MIN, -TWO_63), Ordering::Equal);
+        assert_eq!(
+            compare_int_float(i64::MIN, -2.0 * TWO_63),
+            Ordering::Greater
+        );
+        assert_eq!(compare_int_float(i64::MIN + 1, -TWO_63), Ordering::Greater);
+        let below = f64::from_bits(TWO_63.to_bits() - 1);
+        assert_eq!(compare_int_float(i64::MAX, below), Ordering::Greater);
+    }
+
+    #[test]
+    fn ints_and_floats_compare_exactly_near_zero() {
+        let subnormal = f64::from_bits(1);
+        assert_eq!(compare_int_float(0, subnormal), Ordering::Less);
+        assert_eq!(compare_int_float(0, -subnormal), Ordering::Greater);
+        assert_eq!(compare_int_float(0, 0.0), Ordering::Equal);
+        assert_eq!(compare_int_float(0, -0.0), Ordering::Equal);
+        assert_eq!(compare_int_float(1, 0.5), Ordering::Greater);
+        assert_eq!(compare_int_float(-1, -0.5), Ordering::Less);
+    }
+
+    #[test]
+    fn ints_sort_before_nan_and_between_infinities() {
+        assert_eq!(compare_int_float(i64::MAX, f64::NAN), Ordering::Less);
+        assert_eq!(compare_int_float(i64::MIN, f64::NAN), Ordering::Less);
+        assert_eq!(compare_int_float(i64::MAX, f64::INFINITY), Ordering::Less);
+        assert_eq!(
+            compare_int_float(i64::MIN, f64::NEG_INFINITY),
+            Ordering::Greater
+        );
+        assert_eq!(compare_floats(f64::NAN, f64::NAN), Ordering::Equal);
+        assert_eq!(compare_floats(f64::INFINITY, f64::NAN), Ordering::Less);
+        assert_eq!(compare_floats(-0.0, 0.0), Ordering::Equal);
+    }
+
+    fn ratio(numerator: i64, denominator: i64) -> Rational {
+        Rational::new(BigInt::from(numerator), BigInt::from(denominator))
+    }
+
+    #[test]
+    fn rationals_and_floats_compare_exactly() {
+        // `0.1` is slightly more than one tenth, and `1/3` has no float.
+        assert_eq!(compare_rational_float(&ratio(1, 10), 0.1), Ordering::Less);
+        assert_eq!(
+            compare_rational_float(&ratio(1, 3), 1.0 / 3.0),
+            Ordering::Greater
+        );
+        assert_eq!(compare_rational_float(&ratio(1, 2), 0.5), Ordering::Equal);
+        assert_eq!(compare_rational_float(&ratio(-5, 2), -2.5), Ordering::Equal);
+        let above = ratio((1 << 53) + 1, 1);
+        assert_eq!(
+            compare_rational_float(&above, (1i64 << 53) as f64),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn rationals_and_floats_compare_exactly_at_2_63() {
+        let two_63 = Rational::from(BigInt::from(1i64).shift_left(63));
+        assert_eq!(compare_rational_float(&two_63, TWO_63), Ordering::Equal);
+        assert_eq!(
+            compare_rational_float(&(-&two_63), -TWO_63),
+            Ordering::Equal
+        );
+        let just_below = &two_63 - &ratio(1, 3);
+        assert_eq!(compare_rational_float(&just_below, TWO_63), Ordering::Less);
+        assert_eq!(
+            compare_rational_float(&(-&just_below), -TWO_63),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn rationals_and_floats_compare_exactly_near_zero() {
+        let subnormal = f64::from_bits(1);
+        // The smallest subnormal is 2^-1074.
+        let exact = Rational::new(BigInt::from(1i64), BigInt::from(1i64).shift_left(1074));
+        assert_eq!(compare_rational_float(&exact, subnormal), Ordering::Equal);
+        let half = &exact * &ratio(1, 2);
+        assert_eq!(compare_rational_float(&half, subnormal), Ordering::Less);
+        assert_eq!(compare_rational_float(&half, 0.0), Ordering::Greater);
+        assert_eq!(compare_rational_float(&(-&half), -0.0), Ordering::Less);
+        assert_eq!(
+            compare_rational_float(&(-&half), -subnormal),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn rationals_sort_before_nan_and_between_infinities() {
+        let huge = Rational::new(BigInt::from(1i64).shift_left(2000), BigInt::from(3i64));
+        assert_eq!(compare_rational_float(&huge, f64::MAX), Ordering::Greater);
+        assert_eq!(compare_rational_float(&huge, f64::INFINITY), Ordering::Less);
+        assert_eq!(compare_rational_float(&huge, f64::NAN), Ordering::Less);
+        assert_eq!(
+            compare_rational_float(&(-&huge), f64::NEG_INFINITY),
+            Ordering::Greater
+        );
+        assert_eq!(compare_rational_float(&(-&huge), f64::NAN), Ordering::Less);
+    }
+}
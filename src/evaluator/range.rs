@@ -0,0 +1,195 @@
+use std::fmt;
+
+/// An arithmetic progression of integers from `start` towards `end`, stepping
+/// by `step`. A positive step counts up and a negative one counts down. Ranges
+/// are never materialized: elements are produced on demand and the length is
+/// computed directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub inclusive: bool,
+}
+
+impl Range {
+    /// Build the range written `[start..end..stride]`, where `stride` is the
+    /// distance between elements and the direction follows from the bounds.
+    pub fn new(start: i64, end: i64, stride: i64, inclusive: bool) -> Range {
+        let step = if end < start { -stride } else { stride };
+        Range {
+            start,
+            end,
+            step,
+            inclusive,
+        }
+    }
+
+    /// The last value the range may reach, or `None` when it is empty.
+    fn last_bound(&self) -> Option<i128> {
+        let (start, end) = (self.start as i128, self.end as i128);
+        let last = match (self.inclusive, self.step > 0) {
+            (true, _) => end,
+            (false, true) => end - 1,
+            (false, false) => end + 1,
+        };
+        let empty = if self.step > 0 {
+            last < start
+        } else {
+            last > start
+        };
+        if empty {
+            None
+        } else {
+            Some(last)
+        }
+    }
+
+    /// The number of elements, computed without iterating. It can be 2^64,
+    /// one more than a `u64` holds, for a range spanning every `i64`.
+    pub fn len(&self) -> u128 {
+        match self.last_bound() {
+            Some(last) => ((last - self.start as i128) / self.step as i128 + 1) as u128,
+            None => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let offset = value as i128 - self.start as i128;
+        let index = offset / self.step as i128;
+        offset % self.step as i128 == 0 && index >= 0 && (index as u128) < self.len()
+    }
+
+    /// The element at `index`, counting from zero, computed without iterating.
+    pub fn get(&self, index: u128) -> Option<i64> {
+        if index >= self.len() {
+            return None;
+        }
+        Some((self.start as i128 + index as i128 * self.step as i128) as i64)
+    }
+
+    pub fn iter(&self) -> RangeIter {
+        RangeIter {
+            next: self.start as i128,
+            step: self.step as i128,
+            remaining: self.len(),
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}..{}", self.start, self.end)?;
+        if self.step.abs() != 1 {
+            write!(f, "..{}", self.step.abs())?;
+        }
+        write!(f, "{}", if self.inclusive { "]" } else { ")" })
+    }
+}
+
+pub struct RangeIter {
+    next: i128,
+    step: i128,
+    remaining: u128,
+}
+
+impl Iterator for RangeIter {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let value = self.next as i64;
+        self.next += self.step;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.remaining).ok();
+        (remaining.unwrap_or(usize::MAX), remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn len_counts_without_iterating() {
+        assert_eq!(Range::new(0, 10, 1, false).len(), 10);
+        assert_eq!(Range::new(0, 10, 1, true).len(), 11);
+        assert_eq!(Range::new(0, 10, 3, true).len(), 4);
+        assert_eq!(Range::new(10, 0, 3, true).len(), 4);
+        assert_eq!(Range::new(10, 0, 1, false).len(), 10);
+        assert_eq!(Range::new(5, 5, 1, false).len(), 0);
+        assert_eq!(Range::new(5, 5, 1, true).len(), 1);
+        assert!(Range::new(5, 5, 2, false).is_empty());
+    }
+
+    #[test]
+    fn len_of_extreme_ranges() {
+        let every = Range::new(i64::MIN, i64::MAX, 1, true);
+        assert_eq!(every.len(), 1 << 64);
+        assert_eq!(Range::new(i64::MAX, i64::MIN, 1, true).len(), 1 << 64);
+        assert_eq!(
+            Range::new(i64::MIN, i64::MAX, 1, false).len(),
+            u64::MAX as u128
+        );
+        assert_eq!(Range::new(i64::MIN, i64::MAX, i64::MAX, true).len(), 3);
+        assert_eq!(Range::new(0, i64::MAX, i64::MAX, false).len(), 1);
+    }
+
+    #[test]
+    fn contains_follows_the_step() {
+        let stepped = Range::new(1, 10, 3, true);
+        assert!(stepped.contains(1) && stepped.contains(7) && stepped.contains(10));
+        assert!(!stepped.contains(2) && !stepped.contains(13) && !stepped.contains(-2));
+        let descending = Range::new(10, 0, 4, false);
+        assert!(descending.contains(10) && descending.contains(2));
+        assert!(!descending.contains(0) && !descending.contains(14));
+        let exclusive = Range::new(0, 3, 1, false);
+        assert!(exclusive.contains(2) && !exclusive.contains(3));
+        assert!(!Range::new(3, 3, 1, false).contains(3));
+        let every = Range::new(i64::MIN, i64::MAX, 1, true);
+        assert!(every.contains(i64::MIN) && every.contains(i64::MAX));
+        assert!(!Range::new(i64::MIN, i64::MAX, 1, false).contains(i64::MAX));
+    }
+
+    #[test]
+    fn get_computes_elements_from_the_ends() {
+        let stepped = Range::new(1, 10, 3, true);
+        assert_eq!(stepped.get(0), Some(1));
+        assert_eq!(stepped.get(3), Some(10));
+        assert_eq!(stepped.get(4), None);
+        let descending = Range::new(10, 0, 4, false);
+        assert_eq!(descending.get(2), Some(2));
+        assert_eq!(descending.get(3), None);
+        assert_eq!(Range::new(0, 0, 1, false).get(0), None);
+        let every = Range::new(i64::MIN, i64::MAX, 1, true);
+        assert_eq!(every.get(0), Some(i64::MIN));
+        assert_eq!(every.get(u64::MAX as u128), Some(i64::MAX));
+        assert_eq!(every.get(1 << 64), None);
+        assert_eq!(
+            Range::new(i64::MAX, i64::MIN, 1, true).get(u64::MAX as u128),
+            Some(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn iter_matches_get() {
+        let descending = Range::new(10, -2, 4, true);
+        let elements: Vec<i64> = descending.iter().collect();
+        assert_eq!(elements, [10, 6, 2, -2]);
+        let every = Range::new(i64::MIN, i64::MAX, 1, true);
+        assert_eq!(
+            every.iter().take(2).collect::<Vec<_>>(),
+            [i64::MIN, i64::MIN + 1]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::bigint::BigInt;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// An exact fraction, kept in lowest terms with a positive denominator so
+/// that equal values have equal fields. Its parts are integers of any size,
+/// so arithmetic on it never overflows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    numerator: BigInt,
+    denominator: BigInt,
+}
+
+impl Rational {
+    /// `numerator / denominator` in lowest terms. The denominator must not
+    /// be zero.
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Rational {
+        debug_assert!(!denominator.is_zero(), "zero denominator");
+        let mut divisor = numerator.gcd(&denominator);
+        if denominator.is_negative() {
+            divisor = -&divisor;
+        }
+        Rational {
+            numerator: numerator.div_rem(&divisor).0,
+            denominator: denominator.div_rem(&divisor).0,
+        }
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        &self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == BigInt::from(1i64)
+    }
+
+    /// The whole part, rounding towards zero.
+    pub fn trunc(&self) -> BigInt {
+        self.numerator.div_rem(&self.denominator).0
+    }
+
+    /// The quotient is taken to 64 significant bits before converting, so
+    /// that parts too large for a Float on their own still give its value.
+    pub fn to_f64(&self) -> f64 {
+        let shift = (self.denominator.bits() + 64).saturating_sub(self.numerator.bits());
+        let quotient = self
+            .numerator
+            .shift_left(shift)
+            .div_rem(&self.denominator)
+            .0;
+        // Scale back in two steps, as `2^-shift` alone may underflow where
+        // the result does not. Past 2^-2200 both round to zero anyway.
+        let shift = shift.min(2200) as i32;
+        quotient.to_f64() * 2f64.powi(-shift / 2) * 2f64.powi(shift / 2 - shift)
+    }
+
+    /// Raise to a power by repeated squaring.
+    pub fn pow(&self, exponent: u32) -> Rational {
+        Rational {
+            numerator: self.numerator.pow(exponent),
+            denominator: self.denominator.pow(exponent),
+        }
+    }
+
+    /// One over a nonzero value.
+    pub fn recip(&self) -> Rational {
+        Rational::new(self.denominator.clone(), self.numerator.clone())
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Rational {
+        Rational::from(BigInt::from(value))
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(value: BigInt) -> Rational {
+        Rational {
+            numerator: value,
+            denominator: BigInt::from(1i64),
+        }
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational {
+            numerator: -&self.numerator,
+            denominator: self.denominator.clone(),
+        }
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+
+    fn add(self, other: &Rational) -> Rational {
+        Rational::new(
+            &(&self.numerator * &other.denominator) + &(&other.numerator * &self.denominator),
+            &self.denominator * &other.denominator,
+        )
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+
+    fn sub(self, other: &Rational) -> Rational {
+        self + &-other
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, other: &Rational) -> Rational {
+        Rational::new(
+            &self.numerator * &other.numerator,
+            &self.denominator * &other.denominator,
+        )
+    }
+}
+
+/// Division by a nonzero `other`.
+impl Div for &Rational {
+    type Output = Rational;
+
+    fn div(self, other: &Rational) -> Rational {
+        Rational::new(
+            &self.numerator * &other.denominator,
+            &self.denominator * &other.numerator,
+        )
+    }
+}
+
+/// The remainder of dividing by a nonzero `other`, taking the quotient
+/// rounded towards zero as `%` does for integers.
+impl Rem for &Rational {
+    type Output = Rational;
+
+    fn rem(self, other: &Rational) -> Rational {
+        let dividend = &self.numerator * &other.denominator;
+        let divisor = &self.denominator * &other.numerator;
+        Rational::new(
+            dividend.div_rem(&divisor).1,
+            &self.denominator * &other.denominator,
+        )
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares `a/b` with `c/d` as `a*d` with `c*b`, exact since both
+/// denominators are positive.
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        (&self.numerator * &other.denominator).cmp(&(&other.numerator * &self.denominator))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numerator: i64, denominator: i64) -> Rational {
+        Rational::new(BigInt::from(numerator), BigInt::from(denominator))
+    }
+
+    fn big(text: &str) -> BigInt {
+        BigInt::parse(text).unwrap()
+    }
+
+    #[test]
+    fn new_reduces_to_lowest_terms() {
+        assert_eq!(ratio(6, 4).to_string(), "3/2");
+        assert_eq!(ratio(0, 5).to_string(), "0");
+        assert_eq!(ratio(0, 5), ratio(0, -3));
+        assert_eq!(ratio(10, 5), Rational::from(2));
+        let third = Rational::new(big("300000000000000000000"), big("900000000000000000000"));
+        assert_eq!(third, ratio(1, 3));
+    }
+
+    #[test]
+    fn the_sign_is_kept_on_the_numerator() {
+        assert_eq!(ratio(1, -2).to_string(), "-1/2");
+        assert_eq!(ratio(-1, -2).to_string(), "1/2");
+        assert_eq!(ratio(1, -2), ratio(-1, 2));
+        assert_eq!(
+            ratio(i64::MIN, -1),
+            Rational::from(big("9223372036854775808"))
+        );
+        assert_eq!(-&ratio(-3, 4), ratio(3, 4));
+    }
+
+    #[test]
+    fn arithmetic_does_not_overflow() {
+        // The harmonic number H_50, whose parts do not fit in 64 bits.
+        let harmonic = (1..=50).fold(Rational::from(0), |sum, n| &sum + &ratio(1, n));
+        assert_eq!(
+            harmonic.to_string(),
+            "13943237577224054960759/3099044504245996706400"
+        );
+        let factorial =
+            (1..=21i64).fold(BigInt::from(1i64), |product, n| &product * &BigInt::from(n));
+        let tiny = Rational::new(BigInt::from(1i64), factorial.clone());
+        assert_eq!(&tiny * &Rational::from(factorial), Rational::from(1));
+        let large = ratio(i64::MAX, 3);
+        assert_eq!(
+            (&large * &large).to_string(),
+            "85070591730234615847396907784232501249/9"
+        );
+        assert_eq!(&(&large + &large) - &large, large);
+        assert_eq!(&ratio(1, 3) / &ratio(-2, 3), ratio(-1, 2));
+        assert_eq!(&ratio(7, 2) % &ratio(-3, 2), ratio(1, 2));
+        assert_eq!(ratio(2, 3).pow(3), ratio(8, 27));
+        assert_eq!(ratio(-2, 3).recip(), ratio(-3, 2));
+    }
+
+    #[test]
+    fn comparison_is_exact() {
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert!(ratio(-1, 2) < ratio(-1, 3));
+        assert_eq!(ratio(2, 4).cmp(&ratio(1, 2)), Ordering::Equal);
+        let (above, below) = (
+            ratio(i64::MAX, i64::MAX - 1),
+            ratio(i64::MAX - 1, i64::MAX - 2),
+        );
+        assert!(above < below);
+        assert!(Rational::from(big("100000000000000000000")) > ratio(i64::MAX, 1));
+    }
+
+    #[test]
+    fn to_f64_handles_parts_too_large_for_a_float() {
+        assert_eq!(ratio(1, 3).to_f64(), 1.0 / 3.0);
+        assert_eq!(ratio(-5, 2).to_f64(), -2.5);
+        let huge = BigInt::from(10i64).pow(400);
+        let value = Rational::new(&huge + &BigInt::from(1i64), &huge * &BigInt::from(4i64));
+        assert_eq!(value.to_f64(), 0.25);
+        assert_eq!(Rational::new(BigInt::from(1i64), huge).to_f64(), 0.0);
+    }
+}
@@ -1,14 +1,27 @@
+use crate::diagnostics::Diagnostic;
+use crate::span::{Span, Spanned};
+use std::fmt;
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Token {
     Illegal,
     Eof,
     Identifier(String),
     String(String),
-    // TODO: Add float parsing (.) in integer
+    /// The text of an interpolated string up to its first `{`, as in the
+    /// `"count: ` of `"count: {n}"`.
+    InterpolationStart(String),
+    /// Text between a `}` and the next `{` of an interpolated string.
+    InterpolationMiddle(String),
+    /// Text from the last `}` of an interpolated string to its closing `"`.
+    InterpolationEnd(String),
     Integer(String),
+    /// A number with a fractional part or an exponent, as in `1.5e-3`.
+    Float(String),
     Comment(String),
     Assign,
     Cons,
+    Concat,
     Plus,
     Ampersand,
     // And,
@@ -32,7 +45,15 @@ pub enum Token {
     Rarrow,
     Lt,
     Gt,
+    LtEq,
+    GtEq,
     Fslash,
+    /// `÷` or `over`, dividing exactly.
+    Over,
+    Union,
+    Intersection,
+    Caret,
+    Member,
     Equal,
     Notequal,
     Bang,
@@ -42,6 +63,9 @@ pub enum Token {
     If,
     For,
     Type,
+    Of,
+    Match,
+    With,
     False,
     True,
     Vbar,
@@ -53,6 +77,79 @@ pub enum Token {
     Fn,
     Let,
     Cardinal,
+    Question,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Token::Illegal => return write!(f, "illegal character"),
+            Token::Eof => return write!(f, "end of input"),
+            Token::Identifier(name) => return write!(f, "`{}`", name),
+            Token::String(value) => return write!(f, "{:?}", value),
+            Token::InterpolationStart(_)
+            | Token::InterpolationMiddle(_)
+            | Token::InterpolationEnd(_) => return write!(f, "string"),
+            Token::Integer(value) | Token::Float(value) => return write!(f, "`{}`", value),
+            Token::Comment(_) => return write!(f, "comment"),
+            Token::Assign => "=",
+            Token::Cons => "::",
+            Token::Concat => "++",
+            Token::Plus => "+",
+            Token::Ampersand => "&",
+            Token::Modulo => "%",
+            Token::Minus => "-",
+            Token::Asterisk => "*",
+            Token::Exponent => "**",
+            Token::Comma => ",",
+            Token::Colon => ":",
+            Token::Semicolon => ";",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Period => ".",
+            Token::Range => "..",
+            Token::LeftParen => "(",
+            Token::LeftBrace => "{",
+            Token::RightParen => ")",
+            Token::RightBrace => "}",
+            Token::Rarrow => "->",
+            Token::Lt => "<",
+            Token::Gt => ">",
+            Token::LtEq => "<=",
+            Token::GtEq => ">=",
+            Token::Fslash => "/",
+            Token::Union => "\\/",
+            Token::Intersection => "/\\",
+            Token::Caret => "^",
+            Token::Member => "in",
+            Token::Over => "over",
+            Token::Equal => "==",
+            Token::Notequal => "!=",
+            Token::Bang => "!",
+            Token::Else => "else",
+            Token::Return => "return",
+            Token::In => "<-",
+            Token::If => "if",
+            Token::For => "for",
+            Token::Type => "type",
+            Token::Of => "of",
+            Token::Match => "match",
+            Token::With => "with",
+            Token::False => "false",
+            Token::True => "true",
+            Token::Vbar => "|",
+            Token::Pipe => "|>",
+            Token::Ok => "Ok",
+            Token::None => "None",
+            Token::Unit => "()",
+            Token::Error => "Error",
+            Token::Fn => "fn",
+            Token::Let => "let",
+            Token::Cardinal => "#",
+            Token::Question => "?",
+        };
+        write!(f, "`{}`", symbol)
+    }
 }
 
 #[derive(Debug)]
@@ -61,6 +158,20 @@ pub struct Lexer {
     cur: usize,
     next_cur: usize,
     ch: char,
+    /// Byte offset of every character, plus one past the last.
+    offsets: Vec<usize>,
+    line: usize,
+    line_start: usize,
+    start: usize,
+    start_line: usize,
+    start_column: usize,
+    diagnostics: Vec<Diagnostic>,
+    /// For each interpolation being read, innermost last, how many `{` it
+    /// has open, so the `}` that resumes its string can be told apart.
+    interpolations: Vec<usize>,
+    /// Whether the last token read was `.`, after which a number is a
+    /// position, so `pair.1.0` reads as two of them rather than a float.
+    after_period: bool,
 }
 
 fn is_whitespace(c: char) -> bool {
@@ -68,26 +179,41 @@ fn is_whitespace(c: char) -> bool {
 }
 
 fn is_numeric(c: char) -> bool {
-    ('0'..='9').contains(&c)
+    c.is_ascii_digit()
 }
 
 fn is_alphanumeric(c: char) -> bool {
-    ('a'..='z').contains(&c) || ('A'..='Z').contains(&c) || is_numeric(c) || c == '_'
+    c.is_ascii_lowercase() || c.is_ascii_uppercase() || is_numeric(c) || c == '_'
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Lexer {
+        let mut offsets: Vec<usize> = input.char_indices().map(|(offset, _)| offset).collect();
+        offsets.push(input.len());
         let mut lexer = Lexer {
             input: input.chars().collect(),
             cur: 0,
             next_cur: 0,
             ch: '\0',
+            offsets,
+            line: 1,
+            line_start: 0,
+            start: 0,
+            start_line: 1,
+            start_column: 1,
+            diagnostics: vec![],
+            interpolations: vec![],
+            after_period: false,
         };
         lexer.read();
         lexer
     }
 
     pub fn read(&mut self) {
+        if self.ch == '\n' {
+            self.line += 1;
+            self.line_start = self.next_cur;
+        }
         if self.next_cur >= self.input.len() {
             self.ch = '\0';
         } else {
@@ -97,46 +223,233 @@ impl Lexer {
         self.next_cur += 1;
     }
 
+    fn byte_offset(&self, index: usize) -> usize {
+        self.offsets[index.min(self.input.len())]
+    }
+
+    /// The span from the start of the token being read up to `cur`.
+    fn span(&self) -> Span {
+        self.span_from(self.start, self.start_line, self.start_column)
+    }
+
+    /// The span from the character at index `start`, found on `line` at
+    /// `column`, up to `cur`.
+    fn span_from(&self, start: usize, line: usize, column: usize) -> Span {
+        Span {
+            start: self.byte_offset(start),
+            end: self.byte_offset(self.cur),
+            line,
+            column,
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.cur >= self.input.len()
+    }
+
+    /// Whether `"""` starts at the current character.
+    fn at_triple_quote(&self) -> bool {
+        self.input.get(self.cur..self.cur + 3) == Some(&['"'; 3])
+    }
+
     pub fn peek(&mut self) -> char {
-        self.input[self.next_cur]
+        match self.input.get(self.next_cur) {
+            Some(ch) => *ch,
+            None => '\0',
+        }
     }
 
+    /// Read a string literal from its opening `"` through the closing one,
+    /// decoding escape sequences on the way. A string holding `{` is split
+    /// into parts around the expressions it interpolates.
     pub fn read_string(&mut self) -> Token {
+        if self.at_triple_quote() {
+            return self.read_raw_string();
+        }
         self.read();
-        // on ident essentially
-        let current = self.cur;
+        self.read_string_part(true)
+    }
+
+    /// Read string text up to the closing `"` or the `{` opening an
+    /// interpolation. `opened` tells whether the text follows the opening
+    /// `"` rather than the `}` of an earlier interpolation.
+    fn read_string_part(&mut self, opened: bool) -> Token {
+        let mut value = String::new();
         loop {
-            if is_alphanumeric(self.ch) || is_whitespace(self.ch) {
-                self.read();
-            } else {
-                break;
+            match self.ch {
+                '"' | '{' => break,
+                _ if self.at_end() => {
+                    self.unterminated("\"");
+                    break;
+                }
+                '\\' => value.extend(self.read_escape()),
+                ch => {
+                    value.push(ch);
+                    self.read();
+                }
             }
         }
-
-        // TODO: Force end delimter to be doublequote or error
+        let interpolates = self.ch == '{';
         self.read();
-        Token::String(self.input[current..self.cur - 1].iter().collect::<String>())
+        if interpolates {
+            self.interpolations.push(0);
+        }
+        match (opened, interpolates) {
+            (true, false) => Token::String(value),
+            (true, true) => Token::InterpolationStart(value),
+            (false, true) => Token::InterpolationMiddle(value),
+            (false, false) => Token::InterpolationEnd(value),
+        }
     }
-    pub fn read_number(&mut self) -> Token {
+
+    /// Read a raw string, which runs from `"""` to the next `"""`, possibly
+    /// across lines, and keeps every character between them as written.
+    fn read_raw_string(&mut self) -> Token {
+        for _ in 0..3 {
+            self.read();
+        }
         let current = self.cur;
-        loop {
-            if is_numeric(self.ch) {
+        while !self.at_triple_quote() {
+            if self.at_end() {
+                self.unterminated("\"\"\"");
+                return Token::String(self.input[current..self.cur].iter().collect());
+            }
+            self.read();
+        }
+        let value = self.input[current..self.cur].iter().collect();
+        for _ in 0..3 {
+            self.read();
+        }
+        Token::String(value)
+    }
+
+    /// Read the escape sequence starting at the current `\`, returning the
+    /// character it stands for. An unknown or malformed escape is reported
+    /// and stands for nothing.
+    fn read_escape(&mut self) -> Option<char> {
+        let (start, line, column) = (self.cur, self.line, self.cur - self.line_start + 1);
+        self.read();
+        let escaped = match self.ch {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            '"' => Some('"'),
+            '{' => Some('{'),
+            '}' => Some('}'),
+            '\\' => Some('\\'),
+            'u' => return self.read_unicode_escape(start, line, column),
+            // Leave the end of input for the string to report as unterminated.
+            _ if self.at_end() => return None,
+            _ => None,
+        };
+        self.read();
+        if escaped.is_none() {
+            let span = self.span_from(start, line, column);
+            let sequence: String = self.input[start..self.cur].iter().collect();
+            self.diagnostics.push(
+                Diagnostic::error(format!("unknown escape sequence `{}`", sequence), span)
+                    .with_label("not a valid escape")
+                    .with_hint("use `\\\\` for a literal backslash"),
+            );
+        }
+        escaped
+    }
+
+    /// Read the rest of `\u{...}`, from the `u`, which names a character by
+    /// one to six hexadecimal digits.
+    fn read_unicode_escape(&mut self, start: usize, line: usize, column: usize) -> Option<char> {
+        self.read();
+        let mut digits = String::new();
+        let closed = if self.ch == '{' {
+            self.read();
+            while self.ch.is_ascii_hexdigit() {
+                digits.push(self.ch);
                 self.read();
+            }
+            self.ch == '}'
+        } else {
+            false
+        };
+        if closed {
+            self.read();
+        }
+        let escaped = match closed && (1..=6).contains(&digits.len()) {
+            true => u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32),
+            false => None,
+        };
+        if escaped.is_none() {
+            let span = self.span_from(start, line, column);
+            let label = if closed && (1..=6).contains(&digits.len()) {
+                "not a unicode scalar value"
             } else {
-                break;
+                "expected one to six hex digits in braces, as in `\\u{1F600}`"
+            };
+            self.diagnostics
+                .push(Diagnostic::error("invalid unicode escape", span).with_label(label));
+        }
+        escaped
+    }
+
+    /// Report a string opened at the start of the current token that reaches
+    /// the end of input before its closing `delimiter`.
+    fn unterminated(&mut self, delimiter: &str) {
+        let span = Span {
+            end: self.byte_offset(self.start + 1),
+            ..self.span()
+        };
+        self.diagnostics.push(
+            Diagnostic::error("unterminated string", span)
+                .with_label("this string is never closed")
+                .with_hint(format!("add a closing `{}`", delimiter)),
+        );
+    }
+
+    /// Read an integer, or a float when a fractional part or an exponent
+    /// follows the digits. A `.` only starts a fraction when a digit follows
+    /// it, which leaves `0..5` a range.
+    pub fn read_number(&mut self) -> Token {
+        let current = self.cur;
+        self.read_digits();
+        let mut float = false;
+        if !self.after_period && self.ch == '.' && is_numeric(self.peek()) {
+            float = true;
+            self.read();
+            self.read_digits();
+        }
+        if !self.after_period && matches!(self.ch, 'e' | 'E') {
+            let sign = usize::from(matches!(self.peek(), '+' | '-'));
+            if self
+                .input
+                .get(self.next_cur + sign)
+                .is_some_and(|c| is_numeric(*c))
+            {
+                float = true;
+                for _ in 0..=sign {
+                    self.read();
+                }
+                self.read_digits();
             }
         }
-        return Token::Integer(self.input[current..self.cur].iter().collect::<String>());
+        let literal = self.input[current..self.cur].iter().collect::<String>();
+        if float {
+            Token::Float(literal)
+        } else {
+            Token::Integer(literal)
+        }
+    }
+
+    fn read_digits(&mut self) {
+        while is_numeric(self.ch) {
+            self.read();
+        }
     }
 
+    /// The rest of the line after `//`, whatever it holds.
     pub fn read_comment(&mut self) -> Token {
         let current = self.cur;
-        loop {
-            if is_alphanumeric(self.ch) || self.ch == ' ' || self.ch == '\t' {
-                self.read();
-            } else {
-                break;
-            }
+        while !self.at_end() && self.ch != '\n' {
+            self.read();
         }
         Token::Comment(self.input[current..self.cur].iter().collect::<String>())
     }
@@ -151,7 +464,7 @@ impl Lexer {
             }
         }
         let literal = self.input[current..self.cur].iter().collect::<String>();
-        return match literal.as_str() {
+        match literal.as_str() {
             "fn" => Token::Fn,
             "let" => Token::Let,
             "return" => Token::Return,
@@ -159,16 +472,43 @@ impl Lexer {
             "if" => Token::If,
             "for" => Token::For,
             "type" => Token::Type,
+            "of" => Token::Of,
+            "match" => Token::Match,
+            "with" => Token::With,
             "true" => Token::True,
             "false" => Token::False,
+            "in" => Token::Member,
+            "over" => Token::Over,
             "Ok" => Token::Ok,
             "None" => Token::None,
             "Error" => Token::Error,
             _ => Token::Identifier(literal),
-        };
+        }
+    }
+
+    /// Read the next token along with the span of source it covers.
+    pub fn advance(&mut self) -> Spanned<Token> {
+        let token = self.read_token();
+        self.after_period = token == Token::Period;
+        let span = self.span();
+        if token == Token::Illegal {
+            self.diagnostics.push(
+                Diagnostic::error(
+                    format!("unexpected character `{}`", self.input[self.start]),
+                    span,
+                )
+                .with_label("not part of any token"),
+            );
+        }
+        Spanned::new(token, span)
+    }
+
+    /// Problems found in the source while reading tokens, in source order.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
-    pub fn advance(&mut self) -> Token {
+    fn read_token(&mut self) -> Token {
         loop {
             if is_whitespace(self.ch) {
                 self.read()
@@ -176,6 +516,9 @@ impl Lexer {
                 break;
             }
         }
+        self.start = self.cur;
+        self.start_line = self.line;
+        self.start_column = self.cur - self.line_start + 1;
         let token: Token = match self.ch {
             '=' => {
                 if self.peek() == '=' {
@@ -196,7 +539,14 @@ impl Lexer {
             }
             ')' => Token::RightParen,
             ',' => Token::Comma,
-            '+' => Token::Plus,
+            '+' => {
+                if self.peek() == '+' {
+                    self.read();
+                    Token::Concat
+                } else {
+                    Token::Plus
+                }
+            }
             '-' => {
                 if self.peek() == '>' {
                     self.read();
@@ -241,6 +591,9 @@ impl Lexer {
                 if self.peek() == '-' {
                     self.read();
                     Token::In
+                } else if self.peek() == '=' {
+                    self.read();
+                    Token::LtEq
                 } else {
                     Token::Lt
                 }
@@ -251,10 +604,28 @@ impl Lexer {
                     self.read();
                     let comment = self.read_comment();
                     return comment;
+                } else if self.peek() == '\\' {
+                    self.read();
+                    Token::Intersection
                 } else {
                     Token::Fslash
                 }
             }
+            '\\' => {
+                if self.peek() == '/' {
+                    self.read();
+                    Token::Union
+                } else {
+                    Token::Illegal
+                }
+            }
+            '∪' => Token::Union,
+            '∩' => Token::Intersection,
+            '∈' => Token::Member,
+            '÷' => Token::Over,
+            '⊆' => Token::LtEq,
+            '⊇' => Token::GtEq,
+            '^' => Token::Caret,
             '[' => Token::LeftBracket,
             ']' => Token::RightBracket,
             '*' => {
@@ -267,13 +638,38 @@ impl Lexer {
             }
             '&' => Token::Ampersand,
             '#' => Token::Cardinal,
+            '?' => Token::Question,
             '%' => Token::Modulo,
-            '>' => Token::Gt,
-            '{' => Token::LeftBrace,
-            '}' => Token::RightBrace,
+            '>' => {
+                if self.peek() == '=' {
+                    self.read();
+                    Token::GtEq
+                } else {
+                    Token::Gt
+                }
+            }
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                Token::LeftBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                // The `}` closing an interpolation resumes its string.
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.read();
+                    return self.read_string_part(false);
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    Token::RightBrace
+                }
+                None => Token::RightBrace,
+            },
             '"' => return self.read_string(),
             '0'..='9' => return self.read_number(),
-            'a'..='z' | 'A'..='Z' => return self.read_identifier(),
+            'a'..='z' | 'A'..='Z' | '_' => return self.read_identifier(),
             '\0' => Token::Eof,
             _ => Token::Illegal,
         };
@@ -282,3 +678,219 @@ impl Lexer {
         token
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every token of `input` up to the end, without spans.
+    fn tokens(input: &str) -> Vec<Token> {
+        let mut lexer = Lexer::new(input);
+        let mut tokens = vec![];
+        loop {
+            match lexer.advance().node {
+                Token::Eof => return tokens,
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    /// The messages and labels of what the lexer reported on `input`.
+    fn errors(input: &str) -> Vec<(String, Option<String>)> {
+        let mut lexer = Lexer::new(input);
+        while lexer.advance().node != Token::Eof {}
+        lexer
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.message.clone(), diagnostic.label.clone()))
+            .collect()
+    }
+
+    fn string(value: &str) -> Token {
+        Token::String(value.to_string())
+    }
+
+    #[test]
+    fn comments_run_to_the_end_of_the_line() {
+        assert_eq!(
+            tokens("x; // first, then (second) value\ny"),
+            [
+                Token::Identifier("x".to_string()),
+                Token::Semicolon,
+                Token::Comment(" first, then (second) value".to_string()),
+                Token::Identifier("y".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn strings_hold_any_character() {
+        assert_eq!(
+            tokens(r#""Hello, world! a-b (c)""#),
+            [string("Hello, world! a-b (c)")]
+        );
+        assert_eq!(tokens("\"línea\nnueva ∀\""), [string("línea\nnueva ∀")]);
+        assert!(errors(r#""Hello, world!""#).is_empty());
+    }
+
+    #[test]
+    fn escapes_stand_for_their_character() {
+        assert_eq!(
+            tokens(r#""a\nb\tc\"d\\e\{f\}""#),
+            [string("a\nb\tc\"d\\e{f}")]
+        );
+        assert!(errors(r#""\n\t\"\\""#).is_empty());
+    }
+
+    #[test]
+    fn unknown_escapes_are_reported() {
+        assert_eq!(tokens(r#""a\qb""#), [string("ab")]);
+        assert_eq!(
+            errors(r#""a\qb""#),
+            [(
+                "unknown escape sequence `\\q`".to_string(),
+                Some("not a valid escape".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn unicode_escapes_name_a_character() {
+        assert_eq!(tokens(r#""\u{41}\u{1F600}\u{00e9}""#), [string("A😀é")]);
+        assert_eq!(tokens(r#""\u{10FFFF}""#), [string("\u{10FFFF}")]);
+        assert!(errors(r#""\u{41}\u{1F600}""#).is_empty());
+    }
+
+    #[test]
+    fn invalid_unicode_escapes_are_reported() {
+        let malformed =
+            Some("expected one to six hex digits in braces, as in `\\u{1F600}`".to_string());
+        let not_scalar = Some("not a unicode scalar value".to_string());
+        for (input, label) in [
+            (r#""\u{}""#, &malformed),
+            (r#""\u{1234567}""#, &malformed),
+            (r#""\u41""#, &malformed),
+            (r#""\u{12x}""#, &malformed),
+            (r#""\u{D800}""#, &not_scalar),
+            (r#""\u{DFFF}""#, &not_scalar),
+            (r#""\u{110000}""#, &not_scalar),
+        ] {
+            assert_eq!(
+                errors(input),
+                [("invalid unicode escape".to_string(), label.clone())],
+                "{}",
+                input
+            );
+        }
+        assert_eq!(tokens(r#""a\u{D800}b""#), [string("ab")]);
+    }
+
+    #[test]
+    fn raw_strings_keep_their_text_as_written() {
+        assert_eq!(
+            tokens("\"\"\"line \\n \"quoted\" {x}\nnext\"\"\" y"),
+            [
+                string("line \\n \"quoted\" {x}\nnext"),
+                Token::Identifier("y".to_string())
+            ]
+        );
+        assert_eq!(tokens(r#""""""""#), [string("")]);
+        assert!(errors("\"\"\"a\nb\"\"\"").is_empty());
+    }
+
+    #[test]
+    fn unterminated_strings_are_reported() {
+        let unterminated = (
+            "unterminated string".to_string(),
+            Some("this string is never closed".to_string()),
+        );
+        assert_eq!(tokens("\"abc"), [string("abc")]);
+        for input in ["\"abc", "\"abc\\", "\"\"\"abc\"\""] {
+            assert_eq!(errors(input), std::slice::from_ref(&unterminated));
+        }
+        assert_eq!(tokens("\"\"\"abc\"\""), [string("abc\"\"")]);
+    }
+
+    #[test]
+    fn interpolations_split_a_string_into_parts() {
+        let identifier = |name: &str| Token::Identifier(name.to_string());
+        assert_eq!(
+            tokens(r#""count: {n}""#),
+            [
+                Token::InterpolationStart("count: ".to_string()),
+                identifier("n"),
+                Token::InterpolationEnd("".to_string()),
+            ]
+        );
+        assert_eq!(
+            tokens(r#""{a} and {b}!""#),
+            [
+                Token::InterpolationStart("".to_string()),
+                identifier("a"),
+                Token::InterpolationMiddle(" and ".to_string()),
+                identifier("b"),
+                Token::InterpolationEnd("!".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolations_nest() {
+        assert_eq!(
+            tokens(r#""a {f "b {x}"} c""#),
+            [
+                Token::InterpolationStart("a ".to_string()),
+                Token::Identifier("f".to_string()),
+                Token::InterpolationStart("b ".to_string()),
+                Token::Identifier("x".to_string()),
+                Token::InterpolationEnd("".to_string()),
+                Token::InterpolationEnd(" c".to_string()),
+            ]
+        );
+        // Braces inside the expression pair up before the one closing it.
+        assert_eq!(
+            tokens(r#""{ {a: 1} }.""#),
+            [
+                Token::InterpolationStart("".to_string()),
+                Token::LeftBrace,
+                Token::Identifier("a".to_string()),
+                Token::Colon,
+                Token::Integer("1".to_string()),
+                Token::RightBrace,
+                Token::InterpolationEnd(".".to_string()),
+            ]
+        );
+        assert!(errors(r#""a {f "b {x}"} c""#).is_empty());
+    }
+
+    #[test]
+    fn unterminated_interpolations_are_reported() {
+        let unterminated = (
+            "unterminated string".to_string(),
+            Some("this string is never closed".to_string()),
+        );
+        assert_eq!(
+            tokens(r#""a {x} b"#),
+            [
+                Token::InterpolationStart("a ".to_string()),
+                Token::Identifier("x".to_string()),
+                Token::InterpolationEnd(" b".to_string()),
+            ]
+        );
+        assert_eq!(errors(r#""a {x} b"#), std::slice::from_ref(&unterminated));
+        assert_eq!(
+            errors(r#""a {f "b"} c"#),
+            std::slice::from_ref(&unterminated)
+        );
+        // Input ending inside the expression leaves the missing `}` for the
+        // parser to report.
+        assert_eq!(
+            tokens(r#""a {x"#),
+            [
+                Token::InterpolationStart("a ".to_string()),
+                Token::Identifier("x".to_string()),
+            ]
+        );
+        assert!(errors(r#""a {x"#).is_empty());
+    }
+}
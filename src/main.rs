@@ -1,36 +1,148 @@
 pub mod ast;
+pub mod bigint;
+pub mod checker;
+pub mod diagnostics;
 pub mod evaluator;
 pub mod lexer;
 pub mod parser;
 pub mod repl;
+pub mod span;
+use crate::diagnostics::Renderer;
+use crate::evaluator::object::Object;
 use crate::lexer::*;
 use std::env;
 use std::fs::read_to_string;
 use std::io;
+use std::thread;
+
+/// Stack for the interpreter thread per nested call it allows, so that
+/// reaching the depth limit stops with an error rather than overflowing.
+/// Unoptimized builds have much larger frames.
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) {
+    1 << 17
+} else {
+    1 << 15
+};
+
+/// Stack for the interpreter thread besides its nested calls.
+const BASE_STACK: usize = 1 << 23;
+
+/// What to run, as given on the command line.
+struct Options {
+    /// A `.gdl` file, or `repl` or `eval` for an interactive session.
+    target: String,
+    max_depth: usize,
+    /// Whether to color diagnostics, from `--color=auto|always|never`.
+    color: bool,
+}
+
+impl Options {
+    /// Read `[--max-depth N] [--color WHEN] <target>`, or `None` if they are
+    /// malformed.
+    fn parse(args: &[String]) -> Option<Options> {
+        let mut target = None;
+        let mut max_depth = evaluator::MAX_DEPTH;
+        let mut color = "auto";
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--max-depth" {
+                max_depth = args.next()?.parse().ok()?;
+            } else if let Some(depth) = arg.strip_prefix("--max-depth=") {
+                max_depth = depth.parse().ok()?;
+            } else if arg == "--color" {
+                color = args.next()?;
+            } else if let Some(when) = arg.strip_prefix("--color=") {
+                color = when;
+            } else if target.is_none() {
+                target = Some(arg.clone());
+            } else {
+                return None;
+            }
+        }
+        let color = match color {
+            "auto" => diagnostics::stderr_supports_color(),
+            "always" => true,
+            "never" => false,
+            _ => return None,
+        };
+        Some(Options {
+            target: target?,
+            max_depth,
+            color,
+        })
+    }
+
+    fn stack_size(&self) -> usize {
+        self.max_depth
+            .saturating_mul(STACK_PER_CALL)
+            .saturating_add(BASE_STACK)
+    }
+}
 
 fn main() -> io::Result<()> {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: {} [filename.gdl]", args[0]);
+    let Some(options) = Options::parse(&args[1..]) else {
+        eprintln!(
+            "Usage: {} [--max-depth N] [--color auto|always|never] [filename.gdl | repl | eval]",
+            args[0]
+        );
         std::process::exit(1);
+    };
+    let (max_depth, stack_size) = (options.max_depth, options.stack_size());
+    let interpreter = match thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || run(options))
+    {
+        Ok(interpreter) => interpreter,
+        Err(error) => {
+            eprintln!(
+                "error: cannot reserve {} MiB of stack for a depth of {}: {}",
+                stack_size >> 20,
+                max_depth,
+                error
+            );
+            std::process::exit(1);
+        }
+    };
+    match interpreter.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
     }
+}
 
-    let filename = &args[1];
+fn run(options: Options) -> io::Result<()> {
+    let filename = &options.target;
     if filename == "repl" {
-        repl::start(false);
+        repl::start(false, options.max_depth, options.color);
         return Ok(());
     } else {
         if filename == "eval" {
-            repl::start(true);
+            repl::start(true, options.max_depth, options.color);
             return Ok(());
         }
         let contents = read_to_string(filename)?;
-        let read = contents.trim();
-        let lexer = Lexer::new(read);
+        let renderer = Renderer::new(filename, &contents).color(options.color);
+        let lexer = Lexer::new(&contents);
         let mut parser = parser::Parser::new(lexer);
         let program = parser.parse();
-        for statement in program {
-            println!("{:?}", statement);
+        let diagnostics = parser.diagnostics();
+        if !diagnostics.is_empty() {
+            for diagnostic in diagnostics {
+                eprintln!("{}", renderer.render(&diagnostic));
+            }
+            std::process::exit(1);
+        }
+        for warning in checker::Checker::new().check(&program) {
+            eprintln!("{}", renderer.render(&warning));
+        }
+        let mut evaluator = evaluator::Evaluator::new().max_depth(options.max_depth);
+        match evaluator.eval(&program) {
+            Ok(Object::Null) => (),
+            Ok(object) => println!("{}", object),
+            Err(error) => {
+                eprintln!("{}", renderer.render(&error.node.diagnostic(error.span)));
+                std::process::exit(1);
+            }
         }
     }
 
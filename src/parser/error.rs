@@ -0,0 +1,61 @@
+use crate::diagnostics::Diagnostic;
+use crate::lexer::Token;
+use crate::span::Span;
+use std::fmt;
+
+/// What the parser was looking for when it gave up on a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Token(Token),
+    Expression,
+    Identifier,
+    /// A position small enough to index with, as in `pair.0`.
+    Integer,
+    /// A float literal within the range of a 64-bit float.
+    Float,
+    Pattern,
+    /// A field name or string before `:` in a map.
+    Key,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expected::Token(token) => write!(f, "{}", token),
+            Expected::Expression => write!(f, "an expression"),
+            Expected::Identifier => write!(f, "an identifier"),
+            Expected::Integer => write!(f, "a 64-bit integer"),
+            Expected::Float => write!(f, "a finite 64-bit float"),
+            Expected::Pattern => write!(f, "a pattern"),
+            Expected::Key => write!(f, "a field name or string"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub expected: Expected,
+    pub found: Token,
+    /// Where the offending token appears in the source.
+    pub span: Span,
+    pub hint: Option<String>,
+}
+
+impl ParseError {
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string(), self.span)
+            .with_label(format!("expected {}", self.expected));
+        match &self.hint {
+            Some(hint) => diagnostic.with_hint(hint.clone()),
+            None => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -1,33 +1,127 @@
-use crate::{ast::*, lexer::*};
+pub mod error;
+use crate::bigint::BigInt;
+use crate::{ast::*, diagnostics::Diagnostic, lexer::*, span::Span};
+use error::*;
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Precendence {
     Lowest,
+    Pipe,
     Equals,
     Comparison,
+    Cons,
     Sum,
     Product,
     Prefix,
-    Cons,
+    Try,
     Call,
+    Postfix,
 }
 
 pub fn token_to_precedence(token: &Token) -> Precendence {
     match token {
+        Token::Pipe => Precendence::Pipe,
         Token::Equal | Token::Notequal => Precendence::Equals,
-        Token::Lt | Token::Gt => Precendence::Comparison,
-        Token::Plus | Token::Minus => Precendence::Sum,
-        Token::Fslash | Token::Asterisk | Token::Modulo | Token::Exponent => Precendence::Product,
-        Token::Cons => Precendence::Cons,
-        Token::LeftParen => Precendence::Call,
+        Token::Lt | Token::Gt | Token::LtEq | Token::GtEq | Token::Member => {
+            Precendence::Comparison
+        }
+        Token::Plus | Token::Minus | Token::Union | Token::Caret => Precendence::Sum,
+        Token::Fslash
+        | Token::Over
+        | Token::Asterisk
+        | Token::Modulo
+        | Token::Exponent
+        | Token::Intersection => Precendence::Product,
+        Token::Cons | Token::Concat => Precendence::Cons,
+        Token::LeftParen | Token::Unit => Precendence::Call,
+        Token::Period => Precendence::Postfix,
+        // Below calls so that `divide x y?` unwraps the result of the call.
+        Token::Question => Precendence::Try,
         _ => Precendence::Lowest,
     }
 }
 
+/// Whether `name` refers to a variant rather than binding a value in a
+/// pattern.
+fn is_variant_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// Whether `token` can begin an argument applied by juxtaposition, as `x` in
+/// `f x`. A `{` is left out since it opens the body of `if` and `for`.
+fn starts_argument(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::Integer(_)
+            | Token::Float(_)
+            | Token::String(_)
+            | Token::InterpolationStart(_)
+            | Token::True
+            | Token::False
+            | Token::None
+            | Token::Ok
+            | Token::Error
+            | Token::Fn
+            | Token::Cardinal
+            | Token::Bang
+            | Token::LeftParen
+            | Token::LeftBracket
+    )
+}
+
+/// Whether `expression` may be a function applied by juxtaposition. Literals
+/// are left out so that a missing `;` between `10` and `x` is not a call.
+fn is_applicable(expression: &Expression) -> bool {
+    matches!(
+        expression.kind,
+        ExpressionKind::Identifier(_)
+            | ExpressionKind::Call { .. }
+            | ExpressionKind::Postfix(Postfix::Field(_), _)
+            | ExpressionKind::Fn { .. }
+    )
+}
+
+/// Whether `token` can begin the payload of a variant pattern.
+fn starts_pattern(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::Integer(_)
+            | Token::String(_)
+            | Token::True
+            | Token::False
+            | Token::Minus
+            | Token::LeftParen
+            | Token::LeftBrace
+            | Token::LeftBracket
+            | Token::Ok
+            | Token::Error
+            | Token::None
+    )
+}
+
+/// The tag `Ok`, `Error` and `None` go by in patterns, where they act as the
+/// variants of a built-in type.
+fn builtin_tag(token: &Token) -> Option<&'static str> {
+    match token {
+        Token::Ok => Some("Ok"),
+        Token::Error => Some("Error"),
+        Token::None => Some("None"),
+        _ => None,
+    }
+}
+
 #[derive(Debug)]
 pub struct Parser {
     lexer: Lexer,
     current: Token,
     peek: Token,
+    /// The token after `peek`, to tell a map from a block at `{`.
+    lookahead: Token,
+    current_span: Span,
+    peek_span: Span,
+    lookahead_span: Span,
+    errors: Vec<ParseError>,
 }
 
 impl Parser {
@@ -36,8 +130,14 @@ impl Parser {
             lexer,
             current: Token::Eof,
             peek: Token::Eof,
+            lookahead: Token::Eof,
+            current_span: Span::default(),
+            peek_span: Span::default(),
+            lookahead_span: Span::default(),
+            errors: vec![],
         };
 
+        parser.advance();
         parser.advance();
         parser.advance();
         parser
@@ -45,25 +145,127 @@ impl Parser {
 
     pub fn advance(&mut self) {
         // FIXME: Do without clone?
-        self.current = self.peek.clone();
-        self.peek = self.lexer.advance();
+        self.current = std::mem::replace(&mut self.peek, self.lookahead.clone());
+        self.current_span = self.peek_span;
+        self.peek_span = self.lookahead_span;
+        let next = self.lexer.advance();
+        self.lookahead = next.node;
+        self.lookahead_span = next.span;
     }
 
     /// given a Parser object, iterate over the string
     /// and return a valid Program, i.e a collection of
     /// Statements
+    ///
+    /// Statements that fail to parse are left out of the Program and
+    /// the reason is recorded in `errors`.
     pub fn parse(&mut self) -> Program {
         let mut program: Program = vec![];
         while !self.current_token_is(Token::Eof) {
             match self.parse_statement() {
                 Some(statement) => program.push(statement),
-                None => {}
+                None => self.synchronize(),
             };
             self.advance();
         }
         program
     }
 
+    /// The errors collected by `parse`, in source order.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Every problem found in the source so far: characters the lexer could
+    /// not read followed by parse errors, sorted by position.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self.lexer.diagnostics().to_vec();
+        // An illegal token is already reported by the lexer.
+        diagnostics.extend(
+            self.errors
+                .iter()
+                .filter(|error| error.found != Token::Illegal)
+                .map(ParseError::diagnostic),
+        );
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+        diagnostics
+    }
+
+    fn error(&mut self, expected: Expected, found: Token, span: Span) {
+        self.errors.push(ParseError {
+            expected,
+            found,
+            span,
+            hint: None,
+        });
+    }
+
+    /// Attach a hint to the most recently recorded error.
+    fn hint(&mut self, hint: &str) {
+        if let Some(error) = self.errors.last_mut() {
+            error.hint = Some(hint.to_string());
+        }
+    }
+
+    fn current_error(&mut self, expected: Expected) {
+        self.error(expected, self.current.clone(), self.current_span);
+    }
+
+    fn peek_error(&mut self, expected: Expected) {
+        self.error(expected, self.peek.clone(), self.peek_span);
+    }
+
+    /// Wrap `kind` in an Expression spanning from `start` to the current token.
+    fn expression(&self, kind: ExpressionKind, start: Span) -> Expression {
+        Expression {
+            kind,
+            span: start.to(self.current_span),
+        }
+    }
+
+    /// Advance onto `token` if it is next, otherwise record an error.
+    pub fn expect_peek(&mut self, token: Token) -> bool {
+        if self.if_peek_advance(token.clone()) {
+            true
+        } else {
+            self.peek_error(Expected::Token(token));
+            false
+        }
+    }
+
+    /// Skip the rest of a statement that failed to parse so that its
+    /// remaining tokens are not reported again as new statements. Stops on
+    /// the `;` ending the statement or the `}` closing the enclosing block.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.current {
+                Token::Eof => return,
+                Token::Semicolon | Token::RightBrace if depth == 0 => return,
+                Token::LeftBrace => depth += 1,
+                Token::RightBrace => depth -= 1,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    /// Expect the end of a statement, so that tokens left over after it are
+    /// reported rather than read as the next statement. The semicolon may be
+    /// left off before the end of the input or the end of a block.
+    fn expect_statement_end(&mut self) -> bool {
+        if self.if_peek_advance(Token::Semicolon)
+            || self.peek_token_is(Token::Eof)
+            || self.peek_token_is(Token::RightBrace)
+        {
+            true
+        } else {
+            self.peek_error(Expected::Token(Token::Semicolon));
+            self.hint("end the statement with `;` before starting another");
+            false
+        }
+    }
+
     pub fn peek_precendence(&mut self) -> Precendence {
         token_to_precedence(&self.peek)
     }
@@ -82,148 +284,242 @@ impl Parser {
     pub fn if_peek_advance(&mut self, token: Token) -> bool {
         if self.peek_token_is(token.clone()) {
             self.advance();
-            return true;
+            true
         } else {
-            return false;
+            false
         }
     }
 
     pub fn parse_statement(&mut self) -> Option<Statement> {
-        return match self.current {
+        let start = self.current_span;
+        let kind = match self.current {
             Token::Let => self.parse_let_statement(),
             Token::Return => self.parse_return_statement(),
             Token::Comment(_) => self.parse_comment_statement(),
             _ => self.parse_expression_statement(),
-        };
+        }?;
+        Some(Statement {
+            kind,
+            span: start.to(self.current_span),
+        })
     }
 
-    pub fn parse_comment_statement(&mut self) -> Option<Statement> {
+    pub fn parse_comment_statement(&mut self) -> Option<StatementKind> {
         // FIXME: Clone?
-        return match self.current.clone() {
-            Token::Comment(s) => Some(Statement::Comment(s)),
-            _ => return None,
-        };
+        match self.current.clone() {
+            Token::Comment(s) => Some(StatementKind::Comment(s)),
+            _ => None,
+        }
     }
 
-    pub fn parse_expression_statement(&mut self) -> Option<Statement> {
-        match self.parse_expression(Precendence::Lowest) {
-            Some(expr) => {
-                if self.peek_token_is(Token::Semicolon) {
-                    self.advance();
-                }
-                Some(Statement::Expression(expr))
-            }
-            None => None,
+    pub fn parse_expression_statement(&mut self) -> Option<StatementKind> {
+        let expression = self.parse_expression(Precendence::Lowest)?;
+        if !self.expect_statement_end() {
+            return None;
         }
+        Some(StatementKind::Expression(expression))
     }
 
     pub fn parse_expression(&mut self, precendence: Precendence) -> Option<Expression> {
-        let mut left = match self.current {
+        let errors = self.errors.len();
+        let start = self.current_span;
+        let left = match self.current {
             Token::String(_) => self.parse_string_expression(),
+            Token::InterpolationStart(_) => self.parse_interpolation_expression(),
             Token::Identifier(_) => self.parse_identifier_expression(),
             Token::Integer(_) => self.parse_integer_expression(),
+            Token::Float(_) => self.parse_float_expression(),
             Token::True | Token::False => self.parse_boolean_expression(),
             Token::Plus | Token::Bang | Token::Minus | Token::Cardinal => {
                 self.parse_prefix_expression()
             }
             Token::If => self.parse_if_expression(),
+            Token::Match => self.parse_match_expression(),
             Token::Fn => self.parse_function_expression(),
             Token::LeftParen => self.parse_grouped_expression(),
-            Token::LeftBrace => Some(Expression::Literal(Literal::List(
-                self.parse_expression_list(Token::RightBrace)?,
-            ))),
-            Token::LeftBracket => Some(Expression::Literal(Literal::List(
-                self.parse_expression_list(Token::RightBracket)?,
-            ))),
+            Token::LeftBrace => self.parse_brace_expression(),
+            Token::LeftBracket => self.parse_bracket_expression(),
             Token::Type => self.parse_type_expression(),
             Token::Ok => self.parse_ok_expression(),
             Token::For => self.parse_for_expression(),
             Token::Error => self.parse_error_expression(),
-            Token::Unit | Token::None => Some(Expression::None),
-            _ => return None,
+            Token::Unit | Token::None => Some(ExpressionKind::None),
+            _ => None,
+        };
+        let mut left = match left {
+            Some(kind) => self.expression(kind, start),
+            None => {
+                // Report the token the expression started at unless a more
+                // specific error was already recorded while parsing it.
+                if self.errors.len() == errors {
+                    self.current_error(Expected::Expression);
+                }
+                return None;
+            }
         };
-        while !self.peek_token_is(Token::Semicolon) && precendence < self.peek_precendence() {
+        while !self.peek_token_is(Token::Semicolon) && precendence < self.next_precendence(&left) {
             match self.peek {
                 Token::Plus
                 | Token::Minus
                 | Token::Fslash
+                | Token::Over
                 | Token::Asterisk
                 | Token::Exponent
                 | Token::Equal
                 | Token::Notequal
                 | Token::Modulo
                 | Token::Cons
+                | Token::Concat
                 | Token::Lt
-                | Token::Gt => {
+                | Token::Gt
+                | Token::LtEq
+                | Token::GtEq
+                | Token::Union
+                | Token::Intersection
+                | Token::Caret
+                | Token::Member
+                | Token::Pipe => {
+                    self.advance();
+                    left = self.parse_infix_expression(left)?;
+                }
+                Token::Period => {
                     self.advance();
-                    left = self.parse_infix_expression(left.unwrap());
+                    left = self.parse_postfix_expression(left)?;
+                }
+                Token::Question => {
+                    self.advance();
+                    let start = left.span;
+                    left = self
+                        .expression(ExpressionKind::Postfix(Postfix::Try, Box::new(left)), start);
                 }
                 Token::LeftParen => {
-                    // TODO: Find a way to do this without needing parens
                     self.advance();
-                    left = self.parse_call_expression(left.unwrap());
+                    left = self.parse_call_expression(left)?;
+                    // `f (x) y` applies `f` to both `x` and `y`.
+                    if starts_argument(&self.peek) {
+                        if let ExpressionKind::Call { map, domain } = left.kind {
+                            left = self.parse_application(*map, domain)?;
+                        }
+                    }
+                }
+                _ if starts_argument(&self.peek) => {
+                    left = self.parse_application(left, vec![])?;
+                }
+                Token::Unit => {
+                    // `f()` lexes as a single unit token
+                    self.advance();
+                    let start = left.span;
+                    let unit = self.expression(ExpressionKind::Unit, self.current_span);
+                    left = self.expression(
+                        ExpressionKind::Call {
+                            map: Box::new(left),
+                            domain: vec![unit],
+                        },
+                        start,
+                    );
                 }
-                _ => return left,
+                _ => return Some(left),
             }
         }
-        left
+        Some(left)
     }
 
-    pub fn parse_type_expression(&mut self) -> Option<Expression> {
-        let mut types = vec![];
-        println!("curr {:#?} peek {:#?}", self.current, self.peek);
-        if !self.if_peek_advance(Token::Vbar) {
-            return None;
+    /// The precedence of what follows `left`, which binds like a call when it
+    /// is an argument `left` is applied to.
+    fn next_precendence(&mut self, left: &Expression) -> Precendence {
+        if starts_argument(&self.peek) && is_applicable(left) {
+            Precendence::Call
+        } else {
+            self.peek_precendence()
         }
+    }
 
-        self.advance();
-        match self.parse_identifier() {
-            Some(ident) => types.push(ident),
-            None => return None,
-        };
-
-        println!("curr {:#?} peek {:#?}", self.current, self.peek);
-        while self.peek_token_is(Token::Vbar) {
-            self.advance();
+    /// Parse `f x y` as the call `f(x, y)`, adding to the `arguments` already
+    /// parsed. Each argument binds tighter than any operator, so `f x + 1` is
+    /// `f(x) + 1`.
+    pub fn parse_application(
+        &mut self,
+        left: Expression,
+        mut arguments: Vec<Expression>,
+    ) -> Option<Expression> {
+        while starts_argument(&self.peek) {
             self.advance();
+            arguments.push(self.parse_expression(Precendence::Call)?);
+        }
+        let start = left.span;
+        Some(self.expression(
+            ExpressionKind::Call {
+                map: Box::new(left),
+                domain: arguments,
+            },
+            start,
+        ))
+    }
 
-            match self.parse_identifier() {
-                Some(ident) => types.push(ident),
-                None => return None,
-            };
+    pub fn parse_type_expression(&mut self) -> Option<ExpressionKind> {
+        // type | Alive of Int | Dead of Int | Dormant
+        let mut variants = vec![];
+        if !self.expect_peek(Token::Vbar) {
+            return None;
         }
 
-        Some(Expression::Type(types))
+        loop {
+            self.advance();
+            variants.push(self.parse_variant()?);
+            if !self.if_peek_advance(Token::Vbar) {
+                break;
+            }
+        }
+
+        Some(ExpressionKind::Type(variants))
+    }
+
+    fn parse_variant(&mut self) -> Option<Variant> {
+        let tag = match self.parse_identifier()? {
+            Token::Identifier(name) => name,
+            _ => return None,
+        };
+        let payload = if self.if_peek_advance(Token::Of) {
+            self.advance();
+            match self.parse_identifier()? {
+                Token::Identifier(name) => Some(name),
+                _ => return None,
+            }
+        } else {
+            None
+        };
+        Some(Variant { tag, payload })
     }
 
-    pub fn parse_function_expression(&mut self) -> Option<Expression> {
+    pub fn parse_function_expression(&mut self) -> Option<ExpressionKind> {
         self.advance();
         let parameters = if self.current_token_is(Token::Unit) {
             vec![Token::Unit]
         } else {
-            match self.parse_fn_parameters() {
-                Some(params) => params,
-                None => return None,
-            }
+            self.parse_fn_parameters()?
         };
 
-        if !self.if_peek_advance(Token::Rarrow) {
+        if !self.expect_peek(Token::Rarrow) {
             return None;
         }
 
-        let body = if self.if_peek_advance(Token::LeftBrace) {
-            self.parse_block_fn_statement()
+        self.advance();
+        // `fn p -> {name: p}` returns a map rather than running a block.
+        let body = if self.current_token_is(Token::LeftBrace)
+            && !(self.opens_map() || self.lookahead == Token::With)
+        {
+            self.parse_block_fn_statement()?
         } else {
-            self.advance();
-            match self.parse_expression(Precendence::Lowest) {
-                Some(expression) => vec![Statement::Expression(expression)],
-                None => return None,
-            }
+            let expression = self.parse_expression(Precendence::Lowest)?;
+            vec![Statement {
+                span: expression.span,
+                kind: StatementKind::Expression(expression),
+            }]
         };
 
-        Some(Expression::Fn {
+        Some(ExpressionKind::Fn {
             parameter: parameters,
-            body: body,
+            body,
         })
     }
 
@@ -246,76 +542,240 @@ impl Parser {
 
         Some(params)
     }
-    pub fn parse_block_fn_statement(&mut self) -> Program {
+    pub fn parse_block_fn_statement(&mut self) -> Option<Program> {
+        self.parse_block_statement()
+    }
+
+    pub fn parse_block_statement(&mut self) -> Option<Program> {
         self.advance();
         let mut block = vec![];
         while !self.current_token_is(Token::RightBrace) {
             if self.current_token_is(Token::Eof) {
-                return block;
+                self.current_error(Expected::Token(Token::RightBrace));
+                return None;
             }
             match self.parse_statement() {
                 Some(statement) => block.push(statement),
-                None => {}
+                None => {
+                    self.synchronize();
+                    if self.current_token_is(Token::RightBrace) {
+                        continue;
+                    }
+                }
             }
             self.advance();
         }
 
-        block
+        Some(block)
     }
 
-    pub fn parse_block_statement(&mut self) -> Program {
+    pub fn parse_if_expression(&mut self) -> Option<ExpressionKind> {
+        // if x > 10 { x } else { 10 } ;
         self.advance();
-        let mut block = vec![];
-        while !self.current_token_is(Token::RightBrace) {
-            if self.current_token_is(Token::Eof) {
-                return block;
+        let condition = self.parse_expression(Precendence::Lowest)?;
+        if !self.expect_peek(Token::LeftBrace) {
+            if self.peek_token_is(Token::Assign) {
+                self.hint("did you mean `==`?");
             }
-            match self.parse_statement() {
-                Some(statement) => block.push(statement),
-                None => {}
+            return None;
+        }
+        let consuequence = self.parse_block_statement()?;
+        let mut alternative: Option<Program> = None;
+        if self.peek_token_is(Token::Else) {
+            self.advance();
+            if !self.expect_peek(Token::LeftBrace) {
+                return None;
+            }
+
+            alternative = Some(self.parse_block_statement()?);
+        }
+        Some(ExpressionKind::If {
+            condition: Box::new(condition),
+            consequence: consuequence,
+            alternative,
+        })
+    }
+
+    pub fn parse_match_expression(&mut self) -> Option<ExpressionKind> {
+        // match cell | Alive n -> n | Dead _ -> 0 | Dormant -> -1
+        self.advance();
+        let scrutinee = self.parse_expression(Precendence::Lowest)?;
+        let mut arms = vec![];
+        while self.if_peek_advance(Token::Vbar) {
+            self.advance();
+            let start = self.current_span;
+            let pattern = self.parse_pattern()?;
+            let span = start.to(self.current_span);
+            if !self.expect_peek(Token::Rarrow) {
+                return None;
             }
             self.advance();
+            let body = self.parse_expression(Precendence::Lowest)?;
+            arms.push(Arm {
+                pattern,
+                body,
+                span,
+            });
+        }
+        if arms.is_empty() {
+            self.peek_error(Expected::Token(Token::Vbar));
+            self.hint("give the match at least one arm: `| <pattern> -> <expression>`");
+            return None;
         }
 
-        block
+        Some(ExpressionKind::Match {
+            scrutinee: Box::new(scrutinee),
+            arms,
+        })
     }
 
-    pub fn parse_if_expression(&mut self) -> Option<Expression> {
-        // if x > 10 { x } else { 10 } ;
-        self.advance();
-        let condition = match self.parse_expression(Precendence::Lowest) {
-            Some(expression) => expression,
-            None => return None,
+    /// Parse a pattern: a variant with its payload or a single pattern,
+    /// optionally followed by `:: <pattern>`.
+    pub fn parse_pattern(&mut self) -> Option<Pattern> {
+        let head = match &self.current {
+            Token::Identifier(name) if is_variant_name(name) => {
+                let (type_name, tag) = self.parse_variant_name()?;
+                let payload = if starts_pattern(&self.peek) {
+                    self.advance();
+                    Some(Box::new(self.parse_pattern_atom()?))
+                } else {
+                    None
+                };
+                Pattern::Variant {
+                    type_name,
+                    tag,
+                    payload,
+                }
+            }
+            token @ (Token::Ok | Token::Error) => {
+                let tag = builtin_tag(token).unwrap_or_default().to_string();
+                let payload = if starts_pattern(&self.peek) {
+                    self.advance();
+                    Some(Box::new(self.parse_pattern_atom()?))
+                } else {
+                    None
+                };
+                Pattern::Variant {
+                    type_name: None,
+                    tag,
+                    payload,
+                }
+            }
+            _ => self.parse_pattern_atom()?,
         };
-        self.advance();
-        let consuequence = self.parse_block_statement();
-        let mut alternative: Option<Program> = None;
-        if self.peek_token_is(Token::Else) {
+        if self.if_peek_advance(Token::Cons) {
             self.advance();
-            if !self.if_peek_advance(Token::LeftBrace) {
+            let tail = self.parse_pattern()?;
+            return Some(Pattern::Cons(Box::new(head), Box::new(tail)));
+        }
+        Some(head)
+    }
+
+    /// A variant name, optionally qualified by its type as in `Cell.Alive`.
+    fn parse_variant_name(&mut self) -> Option<(Option<String>, String)> {
+        let first = match &self.current {
+            Token::Identifier(name) => name.clone(),
+            _ => return None,
+        };
+        if !self.if_peek_advance(Token::Period) {
+            return Some((None, first));
+        }
+        match &self.peek {
+            Token::Identifier(tag) if is_variant_name(tag) => {
+                let tag = tag.clone();
+                self.advance();
+                Some((Some(first), tag))
+            }
+            _ => {
+                self.peek_error(Expected::Identifier);
+                self.hint("name a variant of the type, as in `Cell.Alive`");
+                None
+            }
+        }
+    }
+
+    /// A pattern that needs no parentheses to be a variant's payload.
+    fn parse_pattern_atom(&mut self) -> Option<Pattern> {
+        let pattern = match &self.current {
+            Token::Identifier(name) if name == "_" => Pattern::Wildcard,
+            Token::Identifier(name) if is_variant_name(name) => {
+                let (type_name, tag) = self.parse_variant_name()?;
+                Pattern::Variant {
+                    type_name,
+                    tag,
+                    payload: None,
+                }
+            }
+            Token::Identifier(name) => Pattern::Binding(name.clone()),
+            token @ (Token::Ok | Token::Error | Token::None) => Pattern::Variant {
+                type_name: None,
+                tag: builtin_tag(token).unwrap_or_default().to_string(),
+                payload: None,
+            },
+            Token::Integer(_) => Pattern::Integer(self.parse_pattern_integer("")?),
+            Token::Minus => {
+                self.advance();
+                Pattern::Integer(self.parse_pattern_integer("-")?)
+            }
+            Token::String(value) => Pattern::String(value.clone()),
+            Token::True => Pattern::Boolean(true),
+            Token::False => Pattern::Boolean(false),
+            Token::LeftParen => {
+                self.advance();
+                let pattern = self.parse_pattern()?;
+                if !self.expect_peek(Token::RightParen) {
+                    return None;
+                }
+                pattern
+            }
+            Token::LeftBrace => Pattern::List(self.parse_pattern_list(Token::RightBrace)?),
+            Token::LeftBracket => Pattern::List(self.parse_pattern_list(Token::RightBracket)?),
+            _ => {
+                self.current_error(Expected::Pattern);
                 return None;
             }
+        };
+        Some(pattern)
+    }
+
+    fn parse_pattern_integer(&mut self, sign: &str) -> Option<BigInt> {
+        match &self.current {
+            Token::Integer(digits) => BigInt::parse(&format!("{}{}", sign, digits)),
+            _ => {
+                self.current_error(Expected::Integer);
+                None
+            }
+        }
+    }
 
-            alternative = Some(self.parse_block_statement());
+    fn parse_pattern_list(&mut self, delimiter: Token) -> Option<Vec<Pattern>> {
+        let mut patterns = vec![];
+        if self.if_peek_advance(delimiter.clone()) {
+            return Some(patterns);
         }
-        return Some(Expression::If {
-            condition: Box::new(condition),
-            consequence: consuequence,
-            alternative: alternative,
-        });
+        self.advance();
+        patterns.push(self.parse_pattern()?);
+        while self.if_peek_advance(Token::Comma) {
+            self.advance();
+            patterns.push(self.parse_pattern()?);
+        }
+        if !self.expect_peek(delimiter) {
+            return None;
+        }
+        Some(patterns)
     }
 
-    pub fn parse_grouped_expression(&mut self) -> Option<Expression> {
+    pub fn parse_grouped_expression(&mut self) -> Option<ExpressionKind> {
         self.advance();
-        let expression = self.parse_expression(Precendence::Lowest);
-        if !self.if_peek_advance(Token::RightParen) {
+        let expression = self.parse_expression(Precendence::Lowest)?;
+        if !self.expect_peek(Token::RightParen) {
             None
         } else {
-            expression
+            Some(expression.kind)
         }
     }
-    pub fn parse_boolean_expression(&mut self) -> Option<Expression> {
-        Some(Expression::Literal(Literal::Boolean(
+    pub fn parse_boolean_expression(&mut self) -> Option<ExpressionKind> {
+        Some(ExpressionKind::Literal(Literal::Boolean(
             self.current_token_is(Token::True),
         )))
     }
@@ -324,20 +784,21 @@ impl Parser {
         let mut parameters = vec![];
         if self.peek_token_is(Token::Unit) {
             self.advance();
-            if !self.if_peek_advance(Token::RightParen) {
+            parameters.push(self.expression(ExpressionKind::Unit, self.current_span));
+            if !self.expect_peek(Token::RightParen) {
                 return None;
             }
-            parameters.push(Expression::Unit);
         } else {
-            parameters = match self.parse_expression_list(Token::RightParen) {
-                Some(params) => params,
-                None => return None,
-            };
+            parameters = self.parse_expression_list(Token::RightParen)?;
         }
-        Some(Expression::Call {
-            map: Box::new(left),
-            domain: parameters,
-        })
+        let start = left.span;
+        Some(self.expression(
+            ExpressionKind::Call {
+                map: Box::new(left),
+                domain: parameters,
+            },
+            start,
+        ))
     }
     pub fn parse_expression_list(&mut self, delimiter: Token) -> Option<Vec<Expression>> {
         let mut list = vec![];
@@ -362,35 +823,222 @@ impl Parser {
         }
 
         // FIXME: make peektokenis accept a reference
-        if !self.if_peek_advance(delimiter.clone()) {
+        if !self.expect_peek(delimiter.clone()) {
             return None;
         }
         Some(list)
     }
+    /// Parse what opens with `{`: a list `{1, 2}`, a map
+    /// `{name: "Ada", "born": 1815}` or an update `{ada with born: 1816}`.
+    pub fn parse_brace_expression(&mut self) -> Option<ExpressionKind> {
+        if self.opens_map() {
+            return Some(ExpressionKind::Literal(Literal::Map(self.parse_fields()?)));
+        }
+        if self.if_peek_advance(Token::RightBrace) {
+            return Some(ExpressionKind::Literal(Literal::List(vec![])));
+        }
+        self.advance();
+        let first = self.parse_expression(Precendence::Lowest)?;
+        if self.if_peek_advance(Token::With) {
+            return Some(ExpressionKind::Update {
+                record: Box::new(first),
+                fields: self.parse_fields()?,
+            });
+        }
+        let mut elements = vec![first];
+        while self.if_peek_advance(Token::Comma) {
+            self.advance();
+            elements.push(self.parse_expression(Precendence::Lowest)?);
+        }
+        if !self.expect_peek(Token::RightBrace) {
+            return None;
+        }
+        Some(ExpressionKind::Literal(Literal::List(elements)))
+    }
+
+    /// Whether the current `{` opens a map, as it does when a key and `:`
+    /// follow it.
+    fn opens_map(&self) -> bool {
+        matches!(self.peek, Token::Identifier(_) | Token::String(_))
+            && self.lookahead == Token::Colon
+    }
+
+    /// Parse the `key: value` pairs after the current token, through the
+    /// closing `}`.
+    fn parse_fields(&mut self) -> Option<Vec<(String, Expression)>> {
+        let mut fields = vec![];
+        loop {
+            self.advance();
+            let key = match &self.current {
+                Token::Identifier(key) | Token::String(key) => key.clone(),
+                _ => {
+                    self.current_error(Expected::Key);
+                    return None;
+                }
+            };
+            if !self.expect_peek(Token::Colon) {
+                return None;
+            }
+            self.advance();
+            fields.push((key, self.parse_expression(Precendence::Lowest)?));
+            if !self.if_peek_advance(Token::Comma) {
+                break;
+            }
+        }
+        if !self.expect_peek(Token::RightBrace) {
+            return None;
+        }
+        Some(fields)
+    }
+
+    /// Parse either a list `[a, b, c]` or a range `[a..b]`, `[a..b)`,
+    /// `[a..b..step]`, which share the opening bracket and first expression.
+    pub fn parse_bracket_expression(&mut self) -> Option<ExpressionKind> {
+        if self.if_peek_advance(Token::RightBracket) {
+            return Some(ExpressionKind::Literal(Literal::List(vec![])));
+        }
+        self.advance();
+        let first = self.parse_expression(Precendence::Lowest)?;
+        if self.if_peek_advance(Token::Range) {
+            return self.parse_range_expression(first);
+        }
+
+        let mut list = vec![first];
+        while self.if_peek_advance(Token::Comma) {
+            self.advance();
+            list.push(self.parse_expression(Precendence::Lowest)?);
+        }
+        if !self.expect_peek(Token::RightBracket) {
+            return None;
+        }
+        Some(ExpressionKind::Literal(Literal::List(list)))
+    }
+
+    pub fn parse_range_expression(&mut self, start: Expression) -> Option<ExpressionKind> {
+        self.advance();
+        let end = self.parse_expression(Precendence::Lowest)?;
+        let step = if self.if_peek_advance(Token::Range) {
+            self.advance();
+            Some(Box::new(self.parse_expression(Precendence::Lowest)?))
+        } else {
+            None
+        };
+
+        // A closing `)` leaves the end out of the range, as in `[0..10)`.
+        let inclusive = if self.if_peek_advance(Token::RightBracket) {
+            true
+        } else if self.if_peek_advance(Token::RightParen) {
+            false
+        } else {
+            self.peek_error(Expected::Token(Token::RightBracket));
+            self.hint("close the range with `]`, or with `)` to exclude its end");
+            return None;
+        };
+
+        Some(ExpressionKind::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            inclusive,
+        })
+    }
+
     pub fn parse_infix_expression(&mut self, left: Expression) -> Option<Expression> {
         let infix = match self.current {
             Token::Plus => Infix::Plus,
             Token::Minus => Infix::Minus,
             Token::Fslash => Infix::Divide,
+            Token::Over => Infix::ExactDivide,
             Token::Asterisk => Infix::Multiply,
             Token::Equal => Infix::Equal,
             Token::Notequal => Infix::NotEqual,
             Token::Lt => Infix::LessThan,
             Token::Gt => Infix::GreaterThan,
+            Token::LtEq => Infix::LessEqual,
+            Token::GtEq => Infix::GreaterEqual,
+            Token::Union => Infix::Union,
+            Token::Intersection => Infix::Intersection,
+            Token::Caret => Infix::SymmetricDifference,
+            Token::Member => Infix::Member,
+            Token::Pipe => Infix::Pipe,
             Token::Modulo => Infix::Modulo,
             Token::Exponent => Infix::Exponent,
             Token::Cons => Infix::Cons,
+            Token::Concat => Infix::Concat,
             _ => return None,
         };
 
-        let precedence = self.curr_precendence();
+        // `::` and `++` are right associative: `1 :: 2 :: xs` is `1 :: (2 :: xs)`.
+        let precedence = match infix {
+            Infix::Cons | Infix::Concat => Precendence::Comparison,
+            _ => self.curr_precendence(),
+        };
+
+        self.advance();
+        let start = left.span;
+        let right = self.parse_expression(precedence)?;
+        Some(self.expression(
+            ExpressionKind::Infix(infix, Box::new(left), Box::new(right)),
+            start,
+        ))
+    }
+
+    /// Parse what follows `.`: a cast as in `xs.set`, a member as in
+    /// `Cell.Dead` or `person.name`, or a position as in `pair.0`.
+    pub fn parse_postfix_expression(&mut self, left: Expression) -> Option<Expression> {
+        let postfix = match &self.peek {
+            Token::LeftBracket => return self.parse_index_expression(left),
+            Token::Identifier(name) if name == "set" => Postfix::Cast(Cast::Set),
+            Token::Identifier(name) if name == "int" => Postfix::Cast(Cast::Int),
+            Token::Identifier(name) if name == "float" => Postfix::Cast(Cast::Float),
+            Token::Identifier(name) if name == "string" => Postfix::Cast(Cast::String),
+            Token::Identifier(name) => Postfix::Field(name.clone()),
+            Token::Integer(digits) => match digits.parse() {
+                Ok(index) => Postfix::Index(index),
+                Err(_) => {
+                    self.peek_error(Expected::Integer);
+                    return None;
+                }
+            },
+            _ => {
+                self.peek_error(Expected::Identifier);
+                return None;
+            }
+        };
+        self.advance();
+        let start = left.span;
+        Some(self.expression(ExpressionKind::Postfix(postfix, Box::new(left)), start))
+    }
 
+    /// Parse `.[i]`, indexing `collection` by a position, or `.[a..b)`,
+    /// slicing it by a range of positions.
+    pub fn parse_index_expression(&mut self, collection: Expression) -> Option<Expression> {
         self.advance();
-        self.parse_expression(precedence)
-            .map(|expr| Expression::Infix(infix, Box::new(left), Box::new(expr)))
+        let bracket = self.current_span;
+        let index = match self.parse_bracket_expression()? {
+            ExpressionKind::Literal(Literal::List(mut positions)) if positions.len() == 1 => {
+                positions.pop()?
+            }
+            kind @ ExpressionKind::Range { .. } => self.expression(kind, bracket),
+            _ => {
+                self.current_error(Expected::Expression);
+                self.hint(
+                    "index by one position, as in `xs.[0]`, or by a range, as in `xs.[0..2)`",
+                );
+                return None;
+            }
+        };
+        let start = collection.span;
+        Some(self.expression(
+            ExpressionKind::Index {
+                collection: Box::new(collection),
+                index: Box::new(index),
+            },
+            start,
+        ))
     }
 
-    pub fn parse_prefix_expression(&mut self) -> Option<Expression> {
+    pub fn parse_prefix_expression(&mut self) -> Option<ExpressionKind> {
         let prefix = match self.current {
             Token::Bang => Prefix::Not,
             Token::Minus => Prefix::Minus,
@@ -402,101 +1050,266 @@ impl Parser {
         self.advance();
 
         self.parse_expression(Precendence::Prefix)
-            .map(|expr| Expression::Prefix(prefix, Box::new(expr)))
+            .map(|expr| ExpressionKind::Prefix(prefix, Box::new(expr)))
     }
 
-    pub fn parse_error_expression(&mut self) -> Option<Expression> {
-        match &self.current {
-            Token::Error => Some(Expression::Error),
-            _ => None,
+    pub fn parse_error_expression(&mut self) -> Option<ExpressionKind> {
+        // Error, Error "div by zero"
+        if !self.current_token_is(Token::Error) {
+            return None;
+        }
+        if !starts_argument(&self.peek) {
+            return Some(ExpressionKind::Error(None));
         }
+        self.advance();
+        self.parse_expression(Precendence::Call)
+            .map(|expression| ExpressionKind::Error(Some(Box::new(expression))))
     }
 
-    pub fn parse_for_expression(&mut self) -> Option<Expression> {
-        //  C  P
+    pub fn parse_for_expression(&mut self) -> Option<ExpressionKind> {
         // for { x <- [0..10] : x * x };
-        // for { x <- [1,2,3] | x = 0 };
-        if !self.if_peek_advance(Token::LeftBrace) {
+        // for { x <- [1,2,3], x > 1 : x };
+        if !self.expect_peek(Token::LeftBrace) {
+            return None;
+        }
+
+        self.advance();
+        let binder = match self.parse_identifier()? {
+            Token::Identifier(name) => name,
+            _ => return None,
+        };
+        if !self.expect_peek(Token::In) {
+            return None;
+        }
+
+        self.advance();
+        let domain = self.parse_expression(Precendence::Lowest)?;
+        let mut filters = vec![];
+        while self.if_peek_advance(Token::Comma) {
+            self.advance();
+            filters.push(self.parse_expression(Precendence::Lowest)?);
+        }
+        if !self.expect_peek(Token::Colon) {
+            return None;
+        }
+
+        self.advance();
+        let map = self.parse_expression(Precendence::Lowest)?;
+        if !self.expect_peek(Token::RightBrace) {
             return None;
         }
 
-        None
+        Some(ExpressionKind::For {
+            binder,
+            domain: Box::new(domain),
+            filters,
+            map: Box::new(map),
+        })
     }
 
-    pub fn parse_ok_expression(&mut self) -> Option<Expression> {
+    pub fn parse_ok_expression(&mut self) -> Option<ExpressionKind> {
         match &self.current {
+            // The payload binds like an argument: `Ok x + 1` is `(Ok x) + 1`.
             Token::Ok => {
                 self.advance();
-                match self.parse_expression(Precendence::Lowest) {
-                    Some(expression) => Some(Expression::Ok(Box::new(expression))),
-                    None => return None,
-                }
+                self.parse_expression(Precendence::Call)
+                    .map(|expression| ExpressionKind::Ok(Box::new(expression)))
             }
             _ => None,
         }
     }
 
-    pub fn parse_integer_expression(&mut self) -> Option<Expression> {
-        let integer = match &self.current {
-            Token::Integer(v) => v.parse::<i64>().unwrap(),
+    pub fn parse_integer_expression(&mut self) -> Option<ExpressionKind> {
+        match &self.current {
+            Token::Integer(digits) => BigInt::parse(digits)
+                .map(|integer| ExpressionKind::Literal(Literal::Integer(integer))),
+            _ => None,
+        }
+    }
+
+    pub fn parse_float_expression(&mut self) -> Option<ExpressionKind> {
+        let float = match &self.current {
+            Token::Float(v) => match v.parse::<f64>() {
+                Ok(float) if float.is_finite() => float,
+                _ => {
+                    self.current_error(Expected::Float);
+                    return None;
+                }
+            },
             _ => return None,
         };
-        return Some(Expression::Literal(Literal::Integer(integer)));
+        Some(ExpressionKind::Literal(Literal::Float(float)))
     }
 
     pub fn parse_identifier(&mut self) -> Option<Identifier> {
-        return Some(self.current.clone());
+        match self.current {
+            Token::Identifier(_) => Some(self.current.clone()),
+            _ => {
+                self.current_error(Expected::Identifier);
+                None
+            }
+        }
     }
 
-    pub fn parse_string_expression(&mut self) -> Option<Expression> {
+    pub fn parse_string_expression(&mut self) -> Option<ExpressionKind> {
         let slice = match &self.current {
             Token::String(s) => s.clone(),
             _ => return None,
         };
-        return Some(Expression::Literal(Literal::String(slice)));
+        Some(ExpressionKind::Literal(Literal::String(slice)))
+    }
+
+    /// Parse an interpolated string, `"count: {n}"`, into its pieces of text
+    /// and the expressions between them.
+    pub fn parse_interpolation_expression(&mut self) -> Option<ExpressionKind> {
+        let mut parts = vec![];
+        loop {
+            let text = match &self.current {
+                Token::InterpolationStart(text)
+                | Token::InterpolationMiddle(text)
+                | Token::InterpolationEnd(text) => text.clone(),
+                _ => return None,
+            };
+            if !text.is_empty() {
+                parts.push(Expression {
+                    kind: ExpressionKind::Literal(Literal::String(text)),
+                    span: self.current_span,
+                });
+            }
+            if matches!(self.current, Token::InterpolationEnd(_)) {
+                break;
+            }
+            self.advance();
+            parts.push(self.parse_expression(Precendence::Lowest)?);
+            if !matches!(
+                self.peek,
+                Token::InterpolationMiddle(_) | Token::InterpolationEnd(_)
+            ) {
+                self.peek_error(Expected::Token(Token::RightBrace));
+                return None;
+            }
+            self.advance();
+        }
+        Some(ExpressionKind::Interpolation(parts))
     }
 
-    pub fn parse_identifier_expression(&mut self) -> Option<Expression> {
-        return Some(Expression::Identifier(self.current.clone()));
+    pub fn parse_identifier_expression(&mut self) -> Option<ExpressionKind> {
+        Some(ExpressionKind::Identifier(self.current.clone()))
     }
 
-    pub fn parse_return_statement(&mut self) -> Option<Statement> {
+    pub fn parse_return_statement(&mut self) -> Option<StatementKind> {
         if !self.current_token_is(Token::Return) {
             return None;
         }
 
         self.advance();
-        let expression = match self.parse_expression(Precendence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
-        };
-        while !self.current_token_is(Token::Semicolon) {
-            self.advance();
+        let expression = self.parse_expression(Precendence::Lowest)?;
+        if !self.expect_statement_end() {
+            return None;
         }
 
-        return Some(Statement::Return(expression));
+        Some(StatementKind::Return(expression))
     }
 
-    pub fn parse_let_statement(&mut self) -> Option<Statement> {
+    pub fn parse_let_statement(&mut self) -> Option<StatementKind> {
         let identifier = match &self.peek {
             Token::Identifier(s) => s.clone(),
-            _ => return None,
+            _ => {
+                self.peek_error(Expected::Identifier);
+                return None;
+            }
         };
         self.advance();
 
-        if !self.if_peek_advance(Token::Assign) {
+        if !self.expect_peek(Token::Assign) {
             return None;
         }
 
         self.advance();
-        let expression = match self.parse_expression(Precendence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
-        };
-        while !self.current_token_is(Token::Semicolon) {
-            self.advance();
+        let expression = self.parse_expression(Precendence::Lowest)?;
+        if !self.expect_statement_end() {
+            return None;
+        }
+
+        Some(StatementKind::Let(identifier, expression))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An expression with its grouping made explicit, as in `(+ (f x) 1)`.
+    fn show(expression: &Expression) -> String {
+        match &expression.kind {
+            ExpressionKind::Identifier(Token::Identifier(name)) => name.clone(),
+            ExpressionKind::Literal(Literal::Integer(value)) => value.to_string(),
+            ExpressionKind::Prefix(prefix, right) => format!("({} {})", prefix, show(right)),
+            ExpressionKind::Infix(infix, left, right) => {
+                format!("({} {} {})", infix, show(left), show(right))
+            }
+            ExpressionKind::Postfix(postfix, left) => format!("({} {})", postfix, show(left)),
+            ExpressionKind::Call { map, domain } => {
+                let arguments: Vec<String> = domain.iter().map(show).collect();
+                format!("({} {})", show(map), arguments.join(" "))
+            }
+            other => panic!("no shorthand for {:?}", other),
         }
+    }
+
+    /// The single expression statement `source` parses to.
+    fn parse(source: &str) -> String {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse();
+        assert!(
+            parser.diagnostics().is_empty(),
+            "{:?}",
+            parser.diagnostics()
+        );
+        match program.as_slice() {
+            [Statement {
+                kind: StatementKind::Expression(expression),
+                ..
+            }] => show(expression),
+            other => panic!("expected one expression, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn application_binds_tighter_than_infix_operators() {
+        assert_eq!(parse("f x + 1"), "(+ (f x) 1)");
+        assert_eq!(parse("f x y * g z"), "(* (f x y) (g z))");
+        assert_eq!(parse("f (x + 1)"), "(f (+ x 1))");
+    }
+
+    #[test]
+    fn application_binds_tighter_than_prefix_operators() {
+        assert_eq!(parse("- f x"), "(- (f x))");
+        assert_eq!(parse("f (-x)"), "(f (- x))");
+    }
+
+    #[test]
+    fn a_minus_after_a_function_subtracts() {
+        assert_eq!(parse("inc -1"), "(- inc 1)");
+        assert_eq!(parse("inc (-1)"), "(inc (- 1))");
+    }
+
+    #[test]
+    fn parenthesized_and_bare_arguments_mix() {
+        assert_eq!(parse("f (x) y"), "(f x y)");
+        assert_eq!(parse("f(x) y"), "(f x y)");
+        assert_eq!(parse("f x (y)"), "(f x y)");
+    }
+
+    #[test]
+    fn application_binds_tighter_than_pipes() {
+        assert_eq!(parse("x |> f y"), "(|> x (f y))");
+        assert_eq!(parse("f x |> g"), "(|> (f x) g)");
+        assert_eq!(parse("x |> f |> g y"), "(|> (|> x f) (g y))");
+    }
 
-        return Some(Statement::Let(identifier, expression));
+    #[test]
+    fn try_applies_to_the_whole_application() {
+        assert_eq!(parse("divide x y?"), "(? (divide x y))");
     }
 }
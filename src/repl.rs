@@ -1,7 +1,9 @@
 use super::*;
 use std::io::{self, Write};
 
-pub fn start(eval: bool) {
+pub fn start(eval: bool, max_depth: usize, color: bool) {
+    let mut ev = evaluator::Evaluator::new().max_depth(max_depth);
+    let mut checker = checker::Checker::new();
     loop {
         print!("$ ");
         let mut input = String::new();
@@ -11,15 +13,25 @@ pub fn start(eval: bool) {
         if read.eq_ignore_ascii_case("exit") {
             break;
         }
+        let renderer = Renderer::new("<repl>", read).color(color);
         let lexer = Lexer::new(read);
         let mut parser = parser::Parser::new(lexer);
         let program = parser.parse();
+        let diagnostics = parser.diagnostics();
+        if !diagnostics.is_empty() {
+            for diagnostic in diagnostics {
+                eprintln!("{}", renderer.render(&diagnostic));
+            }
+            continue;
+        }
+        for warning in checker.check(&program) {
+            eprintln!("{}", renderer.render(&warning));
+        }
         if eval {
-            let mut ev = evaluator::Evaluator::new();
-            let evaluated = ev.eval(&program);
-            match evaluated {
-                Some(object) => println!("{}", object),
-                None => (),
+            match ev.eval(&program) {
+                Ok(Object::Null) => (),
+                Ok(object) => println!("{}", object),
+                Err(error) => eprintln!("{}", renderer.render(&error.node.diagnostic(error.span))),
             }
         } else {
             println!("{:#?}", program);